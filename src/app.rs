@@ -5,15 +5,28 @@
 //! See also the [report] module.
 
 pub mod report;
+#[cfg(feature = "watch")]
+mod watch;
 
-use std::path::PathBuf;
+use std::{env, fs, path::PathBuf};
 
-use ignore::{overrides::OverrideBuilder, WalkBuilder};
+use ignore::{
+    overrides::{Override, OverrideBuilder},
+    WalkBuilder,
+};
 
-use crate::{error::Error, file::File, Config};
+use crate::{
+    cache::{self, Cache, Probes, Status},
+    error::Error,
+    file::{File, Format},
+    git,
+    project::Projects,
+    sorter::Sorter,
+    Config,
+};
 use report::Report;
 
-use self::report::{CheckReport, FixReport, ReportTrait};
+use self::report::{CheckReport, Diagnostics, ExplainReport, FixReport};
 
 /// The crate version.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -52,11 +65,20 @@ impl App {
     /// Does not take into account [version](Config::version) & [list](Config::list) flags.
     /// For this, see [run](crate::run).
     pub fn run(&mut self) -> Result<Report, Error> {
-        let files: Box<dyn Iterator<Item = File>> = if self.config.is_path_file() {
-            Box::new(vec![File::new(&self.config.path)].into_iter())
-        } else {
-            Box::new(self.files()?)
-        };
+        if let Some(path) = &self.config.explain {
+            return Ok(Report::Explain(self.explain(File::new(path))?));
+        }
+
+        if self.config.staged && !self.config.check {
+            return Err("--staged can only be used with --check".into());
+        }
+
+        let files: Box<dyn Iterator<Item = File>> =
+            if self.config.is_path_file() && self.git_selection()?.is_none() {
+                Box::new(vec![File::new(&self.config.path)].into_iter())
+            } else {
+                Box::new(self.files()?)
+            };
 
         let report = if self.config.check {
             Report::Check(self.check(files)?)
@@ -76,22 +98,78 @@ impl App {
     /// # Usage
     ///
     /// This is particularly useful in CI pipelines.
+    ///
+    /// Files which content did not change since a previous run are not parsed again,
+    /// see [Config::cache_location].
+    ///
+    /// Imports which are not understood are reported, as errors with [Config::strict].
     pub fn check<I>(&self, files: I) -> Result<CheckReport, Error>
     where
         I: Iterator<Item = File>,
     {
-        let mut report = CheckReport::default();
+        let mut report = CheckReport {
+            diagnostics: Diagnostics::new(self.config.strict),
+            ..Default::default()
+        };
+        let mut cache = self.load_cache();
+        let mut projects = Projects::default();
 
         for file in files {
-            let data = file.parse()?;
+            let source = file.read()?;
+            let sorter = Sorter::new(&self.config, projects.for_file(file.path()));
+            let hash = cache::hash(&source, &sorter);
+
+            // Parse diagnosed files anyway to report their diagnostics
+            let status = cache
+                .as_ref()
+                .and_then(|c| c.get(file.path(), hash, &sorter))
+                .filter(|status| !status.diagnosed);
+
+            match status {
+                Some(status) if status.sorted && !status.scattered => {
+                    report.ok_files.push(file);
+                    continue;
+                }
+                // Parse anyway to explain the file
+                Some(status) if !self.config.verbose => {
+                    if status.sorted {
+                        report.scattered_files.push(file);
+                    } else {
+                        report.errored_files.push(file);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let data = File::parse_with(&source, Format::of(file.path(), &self.config), sorter);
+            let status = Status::new(&file, &data);
+            report.diagnostics.extend(&file, &data);
 
-            if file.check(&data) {
+            if let Some(cache) = cache.as_mut() {
+                cache.insert(file.path(), hash, status, Probes::new(&data));
+            }
+
+            if status.sorted && !status.scattered {
                 report.ok_files.push(file);
             } else {
-                report.errored_files.push(file);
+                if self.config.verbose {
+                    report
+                        .explanations
+                        .push(ExplainReport::new(file.clone(), &data));
+                }
+                if status.sorted {
+                    report.scattered_files.push(file);
+                } else {
+                    report.errored_files.push(file);
+                }
             }
         }
 
+        if let Some(cache) = cache.as_mut() {
+            cache.save()?;
+        }
+
         Ok(report)
     }
 
@@ -103,54 +181,208 @@ impl App {
     /// # Command line
     ///
     /// Dangerous files can still be sorted with the `--force` flag.
+    ///
+    /// Files which sorted content does not pass the round-trip verification are left untouched
+    /// and reported as failed.
+    ///
+    /// Imports which are not understood are reported, as errors with [Config::strict].
     pub fn fix<I>(&self, files: I) -> Result<FixReport, Error>
     where
         I: Iterator<Item = File>,
     {
-        let mut report = FixReport::default();
+        let mut report = FixReport {
+            diagnostics: Diagnostics::new(self.config.strict),
+            ..Default::default()
+        };
+        let mut cache = self.load_cache();
+        let mut projects = Projects::default();
 
         for file in files {
-            let data = file.parse()?;
+            let source = file.read()?;
+            let sorter = Sorter::new(&self.config, projects.for_file(file.path()));
+            let hash = cache::hash(&source, &sorter);
+
+            // Parse diagnosed files anyway to report their diagnostics
+            let status = cache
+                .as_ref()
+                .and_then(|c| c.get(file.path(), hash, &sorter))
+                .filter(|status| !status.diagnosed);
+
+            if let Some(status) = status {
+                let dangerous = status.dangerous && !self.config.force;
+
+                // Parse anyway to explain the file
+                if dangerous && !self.config.verbose {
+                    report.dangerous_files.push(file);
+                    continue;
+                }
+                if !dangerous && status.formatted {
+                    report.unchanged_files.push(file);
+                    continue;
+                }
+            }
+
+            let data = File::parse_with(&source, Format::of(file.path(), &self.config), sorter);
+            report.diagnostics.extend(&file, &data);
 
             if !data.is_dangerous() || self.config.force {
-                file.fix(&data)?;
-                report.sorted_files.push(file.clone());
+                match file.fix(&data, &self.config) {
+                    // The new content will be cached on the next run
+                    Ok(true) => report.sorted_files.push(file.clone()),
+                    Ok(false) => {
+                        if let Some(cache) = cache.as_mut() {
+                            cache.insert(
+                                file.path(),
+                                hash,
+                                Status::new(&file, &data),
+                                Probes::new(&data),
+                            );
+                        }
+                        report.unchanged_files.push(file.clone());
+                    }
+                    Err(err @ Error::Verification { .. }) => {
+                        eprintln!("{}: {}", file, err);
+                        report.failed_files.push(file.clone());
+                    }
+                    Err(err) => return Err(err),
+                }
             } else {
+                if let Some(cache) = cache.as_mut() {
+                    cache.insert(
+                        file.path(),
+                        hash,
+                        Status::new(&file, &data),
+                        Probes::new(&data),
+                    );
+                }
+                if self.config.verbose {
+                    report
+                        .explanations
+                        .push(ExplainReport::new(file.clone(), &data));
+                }
                 report.dangerous_files.push(file.clone());
             }
         }
 
-        if report.err() {
+        if let Some(cache) = cache.as_mut() {
+            cache.save()?;
+        }
+
+        if !report.dangerous_files.is_empty() {
             Self::print_force_warning(&report.dangerous_files);
         }
 
         Ok(report)
     }
 
+    /// Explains how a file is sorted.
+    ///
+    /// The explanation lists the imports with their group & line, the expected order and the
+    /// comments that make sorting dangerous.
+    ///
+    /// # Command line
+    ///
+    /// This method is called by [App::run] if the `--explain <file>` option is given.
+    pub fn explain(&self, file: File) -> Result<ExplainReport, Error> {
+        let data = file.parse(&self.config)?;
+
+        Ok(ExplainReport::new(file, &data))
+    }
+
     /// Returns an iterator on the files that match the globs in the [App]'s [Config].
-    pub fn files(&self) -> Result<impl Iterator<Item = File>, Error> {
+    ///
+    /// When a git selection (`--changed`, `--staged` or `--since`) is given, files are taken from
+    /// git rather than by walking [Config::path].
+    pub fn files(&self) -> Result<Box<dyn Iterator<Item = File>>, Error> {
+        if let Some(selection) = self.git_selection()? {
+            return Ok(Box::new(self.git_files(&selection)?.into_iter()));
+        }
+
+        Ok(Box::new(
+            WalkBuilder::new(self.config.path.clone())
+                .overrides(self.overrides()?)
+                .build()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_file())
+                .map(|entry| File::new(entry.path())),
+        ))
+    }
+
+    /// Returns the files selected from git, under [Config::path] and matching the globs.
+    fn git_files(&self, selection: &git::Selection) -> Result<Vec<File>, Error> {
+        let base = fs::canonicalize(&self.config.path)?;
+        let current_dir = env::current_dir()?;
+        let overrides = self.overrides()?;
+
+        let files = git::files(&base, selection)?
+            .into_iter()
+            .filter(|path| match path.strip_prefix(&base) {
+                // Config::path is the file itself
+                Ok(relative) if relative.as_os_str().is_empty() => true,
+                Ok(relative) => overrides.matched(relative, false).is_whitelist(),
+                Err(_) => false,
+            })
+            .map(|path| {
+                let path = path
+                    .strip_prefix(&current_dir)
+                    .map_or(path.clone(), PathBuf::from);
+
+                match selection {
+                    git::Selection::Staged => File::staged(path),
+                    _ => File::new(path),
+                }
+            })
+            .collect();
+
+        Ok(files)
+    }
+
+    /// Returns the git selection given in the [Config], if any.
+    fn git_selection(&self) -> Result<Option<git::Selection>, Error> {
+        let selections: Vec<_> = [
+            self.config.changed.then_some(git::Selection::Changed),
+            self.config.staged.then_some(git::Selection::Staged),
+            self.config.since.clone().map(git::Selection::Since),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if selections.len() > 1 {
+            return Err("only one of --changed, --staged and --since can be used".into());
+        }
+
+        Ok(selections.into_iter().next())
+    }
+
+    /// Loads the cache, [None] if it is disabled.
+    fn load_cache(&self) -> Option<Cache> {
+        self.config
+            .cache_location()
+            .map(|location| Cache::load(location, &self.config))
+    }
+
+    /// Builds the globs matcher.
+    fn overrides(&self) -> Result<Override, Error> {
         let mut globs = OverrideBuilder::new(self.config.path.clone());
 
         for glob in self.config.globs.clone() {
             globs.add(&glob)?;
         }
+        if self.config.code_blocks {
+            globs.add("**.md")?;
+        }
 
-        let globs = globs.build()?;
-
-        Ok(WalkBuilder::new(self.config.path.clone())
-            .overrides(globs)
-            .build()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().is_file())
-            .map(|entry| File::new(entry.path())))
+        Ok(globs.build()?)
     }
 
     fn print_force_warning<P>(files: &[P])
     where
         P: Into<PathBuf>,
     {
-        eprintln!("\x1b[31mComments have been located between imports of {} files. Sorting is dangerous.\x1b[m", files.len());
+        eprintln!("\x1b[31mComments or namespace aliases were found between imports of {} files. Sorting is dangerous.\x1b[m", files.len());
         eprintln!("\x1b[36mUse \x1b[1m--force\x1b[0;36m to process those files anyway.\x1b[m");
+        eprintln!("\x1b[36mUse \x1b[1m--verbose\x1b[0;36m or \x1b[1m--explain <file>\x1b[0;36m to see which ones are dangerous.\x1b[m");
         eprintln!();
     }
 }
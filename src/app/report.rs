@@ -3,14 +3,16 @@
 //! A report is a set of data which sums up a run.
 
 mod check_report;
+mod explain_report;
 mod fix_report;
 
 use std::fmt::{Debug, Display};
 
 pub use check_report::CheckReport;
+pub use explain_report::ExplainReport;
 pub use fix_report::FixReport;
 
-use crate::file::File;
+use crate::file::{File, FileData, ParseDiagnostic};
 
 /// This is a wrapper around all reports this app can produce.
 ///
@@ -22,6 +24,8 @@ pub enum Report {
     Fix(FixReport),
     /// A report over a *check* run.
     Check(CheckReport),
+    /// A report explaining a single file.
+    Explain(ExplainReport),
 }
 
 impl ReportTrait for Report {
@@ -29,6 +33,7 @@ impl ReportTrait for Report {
         match self {
             Report::Fix(report) => report.len(),
             Report::Check(report) => report.len(),
+            Report::Explain(report) => report.len(),
         }
     }
 
@@ -36,6 +41,7 @@ impl ReportTrait for Report {
         match self {
             Report::Fix(report) => report.all(),
             Report::Check(report) => report.all(),
+            Report::Explain(report) => report.all(),
         }
     }
 
@@ -43,6 +49,7 @@ impl ReportTrait for Report {
         match self {
             Report::Fix(report) => report.err(),
             Report::Check(report) => report.err(),
+            Report::Explain(report) => report.err(),
         }
     }
 }
@@ -52,6 +59,7 @@ impl Debug for Report {
         match self {
             Report::Fix(report) => Debug::fmt(report, f),
             Report::Check(report) => Debug::fmt(report, f),
+            Report::Explain(report) => Debug::fmt(report, f),
         }
     }
 }
@@ -61,10 +69,83 @@ impl Display for Report {
         match self {
             Report::Fix(report) => Display::fmt(report, f),
             Report::Check(report) => Display::fmt(report, f),
+            Report::Explain(report) => Display::fmt(report, f),
         }
     }
 }
 
+/// Imports which were not understood during a run, see [ParseDiagnostic].
+///
+/// They are warnings, or errors in strict mode (see [Config::strict](crate::Config::strict)).
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    /// The diagnostics, with the file they were found in.
+    pub entries: Vec<(File, ParseDiagnostic)>,
+    /// Whether diagnostics are errors.
+    pub strict: bool,
+}
+
+impl Diagnostics {
+    /// Creates empty diagnostics, which are errors if `strict` is set.
+    pub fn new(strict: bool) -> Self {
+        Self {
+            entries: Vec::new(),
+            strict,
+        }
+    }
+
+    /// Adds the diagnostics of a parsed file.
+    pub fn extend(&mut self, file: &File, data: &FileData) {
+        self.entries.extend(
+            data.diagnostics()
+                .map(|diagnostic| (file.clone(), diagnostic.clone())),
+        );
+    }
+
+    /// Number of diagnostics.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no diagnostics.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Tells whether the diagnostics are errors.
+    pub fn err(&self) -> bool {
+        self.strict && !self.is_empty()
+    }
+
+    /// Writes the summary of the diagnostics, if any.
+    fn fmt_summary(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.is_empty(), self.strict) {
+            (true, _) => Ok(()),
+            (false, true) => write!(f, " \x1b[31m{} parse errors.\x1b[m", self.len()),
+            (false, false) => write!(f, " \x1b[33m{} parse warnings.\x1b[m", self.len()),
+        }
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self.strict {
+            true => "\x1b[31merror\x1b[m",
+            false => "\x1b[33mwarning\x1b[m",
+        };
+
+        for (file, diagnostic) in &self.entries {
+            writeln!(
+                f,
+                "{}:{}:{}: {}: {}",
+                file, diagnostic.line, diagnostic.column, level, diagnostic
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 /// The main report type.
 ///
 /// Every report implement this trait.
@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use crate::file::File;
 
-use super::ReportTrait;
+use super::{Diagnostics, ExplainReport, ReportTrait};
 
 /// Represents the result of a *check* run.
 ///
@@ -13,11 +13,19 @@ pub struct CheckReport {
     pub ok_files: Vec<File>,
     /// Files which imports are not sorted properly.
     pub errored_files: Vec<File>,
+    /// Files which imports are sorted, but with imports after other code.
+    pub scattered_files: Vec<File>,
+    /// Explanations of the files in error.
+    ///
+    /// Only filled in verbose mode.
+    pub explanations: Vec<ExplainReport>,
+    /// Imports which were not understood.
+    pub diagnostics: Diagnostics,
 }
 
 impl ReportTrait for CheckReport {
     fn len(&self) -> usize {
-        self.ok_files.len() + self.errored_files.len()
+        self.ok_files.len() + self.errored_files.len() + self.scattered_files.len()
     }
 
     fn all(&self) -> Vec<File> {
@@ -25,24 +33,44 @@ impl ReportTrait for CheckReport {
 
         res.append(&mut self.ok_files.clone());
         res.append(&mut self.errored_files.clone());
+        res.append(&mut self.scattered_files.clone());
 
         res
     }
 
     fn err(&self) -> bool {
-        !self.errored_files.is_empty()
+        !self.errored_files.is_empty() || !self.scattered_files.is_empty() || self.diagnostics.err()
     }
 }
 
 impl Display for CheckReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for explanation in &self.explanations {
+            writeln!(f, "{}", explanation)?;
+        }
+        write!(f, "{}", self.diagnostics)?;
+
         write!(
             f,
             "{} files checked. {} files ok. {error}{} files badly sorted.\x1b[m",
             self.len(),
             self.ok_files.len(),
             self.errored_files.len(),
-            error = if self.err() { "\x1b[31m" } else { "" }
-        )
+            error = if self.errored_files.is_empty() {
+                ""
+            } else {
+                "\x1b[31m"
+            }
+        )?;
+
+        if !self.scattered_files.is_empty() {
+            write!(
+                f,
+                " \x1b[31m{} files with imports after other code.\x1b[m",
+                self.scattered_files.len()
+            )?;
+        }
+
+        self.diagnostics.fmt_summary(f)
     }
 }
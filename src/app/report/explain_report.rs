@@ -0,0 +1,241 @@
+use std::fmt::Display;
+
+use crate::{
+    file::{File, FileData, ParseDiagnostic},
+    statements::{ImportType, Statement},
+};
+
+use super::ReportTrait;
+
+/// Explains how a file is parsed and why it is badly sorted or dangerous.
+#[derive(Debug)]
+pub struct ExplainReport {
+    /// The explained file.
+    pub file: File,
+    imports: Vec<ExplainedImport>,
+    sorted: bool,
+    expected: Vec<ExplainedImport>,
+    dangerous_comments: Vec<(usize, String)>,
+    /// Imports found after other code, with whether they can be moved up.
+    scattered: Vec<(usize, String, bool)>,
+    diagnostics: Vec<ParseDiagnostic>,
+}
+
+/// An import with its position in the original file and its group.
+#[derive(Debug, Clone)]
+struct ExplainedImport {
+    line: usize,
+    ty: ImportType,
+    code: String,
+}
+
+impl ExplainReport {
+    /// Creates the explanation of a parsed file.
+    ///
+    /// The scripts of container files are explained one after the other.
+    pub fn new(file: File, data: &FileData) -> Self {
+        let mut imports = Vec::new();
+        let mut expected = Vec::new();
+        let mut dangerous_comments = Vec::new();
+        let mut scattered = Vec::new();
+
+        for script in data.scripts() {
+            let script_imports: Vec<_> = script
+                .imports
+                .iter()
+                .zip(&script.import_lines)
+                .map(|(import, line)| ExplainedImport {
+                    line: *line,
+                    ty: script.sorter.ty(import),
+                    code: import.code(),
+                })
+                .collect();
+
+            expected.extend(script.sorter.order(&script.imports).into_iter().map(|i| {
+                ExplainedImport {
+                    code: script.sorter.normalize(&script.imports[i]).code(),
+                    ..script_imports[i].clone()
+                }
+            }));
+            imports.extend(script_imports);
+            scattered.extend(
+                script
+                    .scattered
+                    .iter()
+                    .map(|s| (s.line, s.import.code(), s.movable)),
+            );
+
+            dangerous_comments.extend(
+                script
+                    .statements
+                    .iter()
+                    .filter(|(index, _)| script.is_dangerous_index(**index))
+                    .flat_map(|(index, statements)| {
+                        statements
+                            .iter()
+                            .zip(&script.statement_lines[index])
+                            .map(|(statement, line)| (*line, statement.code()))
+                    }),
+            );
+        }
+        dangerous_comments.sort();
+
+        Self {
+            file,
+            imports,
+            sorted: data.is_sorted(),
+            expected,
+            dangerous_comments,
+            scattered,
+            diagnostics: data.diagnostics().cloned().collect(),
+        }
+    }
+
+    /// Whether the imports are sorted correctly.
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// Whether sorting this file is dangerous.
+    pub fn is_dangerous(&self) -> bool {
+        !self.dangerous_comments.is_empty()
+    }
+
+    /// Whether imports are found after other code.
+    pub fn is_scattered(&self) -> bool {
+        !self.scattered.is_empty()
+    }
+}
+
+impl ReportTrait for ExplainReport {
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn all(&self) -> Vec<File> {
+        vec![self.file.clone()]
+    }
+
+    fn err(&self) -> bool {
+        !self.is_sorted() || self.is_dangerous() || self.is_scattered()
+    }
+}
+
+impl Display for ExplainReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\x1b[1m{}\x1b[m: ", self.file)?;
+        match (self.is_sorted(), self.is_dangerous()) {
+            (true, false) => writeln!(f, "imports sorted.")?,
+            (false, false) => writeln!(f, "\x1b[31mimports badly sorted.\x1b[m")?,
+            (true, true) => writeln!(f, "\x1b[31mimports sorted, dangerous to sort.\x1b[m")?,
+            (false, true) => writeln!(f, "\x1b[31mimports badly sorted, dangerous to sort.\x1b[m")?,
+        }
+
+        writeln!(f, "  Imports:")?;
+        for import in &self.imports {
+            writeln!(f, "{}", import)?;
+        }
+
+        if !self.is_sorted() {
+            writeln!(f, "  Expected order:")?;
+            for import in &self.expected {
+                writeln!(f, "{}", import)?;
+            }
+        }
+
+        if self.is_dangerous() {
+            writeln!(f, "  Comments between imports:")?;
+            for (line, comment) in &self.dangerous_comments {
+                writeln!(f, "    {:>4}  {}", line, comment)?;
+            }
+        }
+
+        if !self.diagnostics.is_empty() {
+            writeln!(f, "  \x1b[33mImports not understood:\x1b[m")?;
+            for diagnostic in &self.diagnostics {
+                writeln!(
+                    f,
+                    "    {:>4}:{:<3} {}",
+                    diagnostic.line, diagnostic.column, diagnostic
+                )?;
+            }
+        }
+
+        if self.is_scattered() {
+            writeln!(f, "  \x1b[31mImports after other code:\x1b[m")?;
+            for (line, code, movable) in &self.scattered {
+                let note = if *movable {
+                    ""
+                } else {
+                    "  (can't be moved up)"
+                };
+                writeln!(f, "    {:>4}  {}{}", line, code, note)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for ExplainedImport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "    {:>4}  {:<12}  {}",
+            self.line,
+            format!("{:?}", self.ty),
+            self.code
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explain(source: &str) -> ExplainReport {
+        ExplainReport::new(File::new("index.js"), &File::parse_source(source))
+    }
+
+    #[test]
+    fn sorted() {
+        let report = explain("import a from 'a';\nimport b from 'b';\n");
+
+        assert!(report.is_sorted());
+        assert!(!report.is_dangerous());
+        assert!(report.ok());
+    }
+
+    #[test]
+    fn expected_order() {
+        let report = explain("import b from './b';\nimport a from 'a';\n");
+
+        assert!(!report.is_sorted());
+        assert_eq!(report.expected[0].line, 2);
+        assert_eq!(report.expected[0].ty, ImportType::Module);
+        assert_eq!(report.expected[1].line, 1);
+        assert_eq!(report.expected[1].ty, ImportType::Local);
+    }
+
+    #[test]
+    fn dangerous_comments() {
+        let report = explain("// ok\nimport a from 'a';\n// not ok\nimport b from 'b';\n");
+
+        assert!(report.is_sorted());
+        assert_eq!(report.dangerous_comments, vec![(3, "// not ok".to_owned())]);
+        assert!(report.err());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let report = explain(
+            "import a from 'a';
+import b from b;
+",
+        );
+
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].line, 2);
+        assert!(report.to_string().contains("Imports not understood"));
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use crate::file::File;
 
-use super::ReportTrait;
+use super::{Diagnostics, ExplainReport, ReportTrait};
 
 /// This represents the result of a *fix* run.
 ///
@@ -11,6 +11,7 @@ use super::ReportTrait;
 /// - left unchanged when they are correctly formatted.
 /// - sorted when when they are not dangerous or when the `force` flag is enabled
 /// - marked as dangerous when they are and the `force` flag is not enabled
+/// - marked as failed when sorting them did not pass the round-trip verification
 #[derive(Debug, Default)]
 pub struct FixReport {
     /// Unchanged files.
@@ -21,11 +22,24 @@ pub struct FixReport {
     pub sorted_files: Vec<File>,
     /// Files marked dangerous likely because they have comments between their imports.
     pub dangerous_files: Vec<File>,
+    /// Files that have not been written because sorting them would have lost or mangled content.
+    ///
+    /// This is an internal error and should be reported.
+    pub failed_files: Vec<File>,
+    /// Explanations of the files in error.
+    ///
+    /// Only filled in verbose mode.
+    pub explanations: Vec<ExplainReport>,
+    /// Imports which were not understood.
+    pub diagnostics: Diagnostics,
 }
 
 impl ReportTrait for FixReport {
     fn len(&self) -> usize {
-        self.sorted_files.len() + self.unchanged_files.len() + self.dangerous_files.len()
+        self.sorted_files.len()
+            + self.unchanged_files.len()
+            + self.dangerous_files.len()
+            + self.failed_files.len()
     }
 
     fn all(&self) -> Vec<File> {
@@ -34,17 +48,23 @@ impl ReportTrait for FixReport {
         res.append(&mut self.sorted_files.clone());
         res.append(&mut self.unchanged_files.clone());
         res.append(&mut self.dangerous_files.clone());
+        res.append(&mut self.failed_files.clone());
 
         res
     }
 
     fn err(&self) -> bool {
-        !self.dangerous_files.is_empty()
+        !self.dangerous_files.is_empty() || !self.failed_files.is_empty() || self.diagnostics.err()
     }
 }
 
 impl Display for FixReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for explanation in &self.explanations {
+            writeln!(f, "{}", explanation)?;
+        }
+        write!(f, "{}", self.diagnostics)?;
+
         write!(
             f,
             "{} files checked. {} files sorted. {err}{} dangerous files.\x1b[m",
@@ -52,6 +72,16 @@ impl Display for FixReport {
             self.sorted_files.len(),
             self.dangerous_files.len(),
             err = if self.err() { "\x1b[31m" } else { "" }
-        )
+        )?;
+
+        if !self.failed_files.is_empty() {
+            write!(
+                f,
+                " \x1b[31m{} files failed verification.\x1b[m",
+                self.failed_files.len()
+            )?;
+        }
+
+        self.diagnostics.fmt_summary(f)
     }
 }
@@ -0,0 +1,429 @@
+//! The watch mode, see [App::watch].
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    env, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    overrides::Override,
+    Match,
+};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::{cache::StableHasher, error::Error, file::File};
+
+use super::{report::Report, App};
+
+/// Time to wait for following events before processing a batch of changes.
+const BATCH_DELAY: Duration = Duration::from_millis(100);
+/// Names of the ignore files read in every directory, by increasing priority.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+impl App {
+    /// Watches [Config::path](crate::Config::path) and checks or fixes files as they change.
+    ///
+    /// Changed files are filtered with the same globs & ignore rules as [App::files].
+    /// Changes caused by jisort's own writes are ignored, so it doesn't loop on them.
+    ///
+    /// This only returns on error.
+    ///
+    /// # Command line
+    ///
+    /// This method is called by [run](crate::run) if the `--watch` flag is given.
+    pub fn watch(&self) -> Result<(), Error> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(Path::new(&self.config.path), RecursiveMode::Recursive)?;
+
+        let mut filter = Filter::new(&self.config.path, self.overrides()?)?;
+        let mut own_writes = HashMap::new();
+
+        loop {
+            let mut changed = BTreeSet::new();
+
+            // Wait for a change, then gather the ones coming right after
+            let event = receiver.recv().map_err(|_| "file watcher stopped")?;
+            Self::collect_changes(event?, &mut changed);
+            while let Ok(event) = receiver.recv_timeout(BATCH_DELAY) {
+                Self::collect_changes(event?, &mut changed);
+            }
+
+            if let Some(report) = self.process(changed, &mut filter, &mut own_writes)? {
+                println!("{}", report);
+            }
+        }
+    }
+
+    /// Checks or fixes the `changed` files which would be processed by a normal run.
+    ///
+    /// Files which content is the one jisort wrote, as recorded in `own_writes`, are skipped, as
+    /// are the ones removed since. Files which can't be read are reported, and skipped too.
+    /// Returns [None] if no file is left.
+    fn process(
+        &self,
+        changed: BTreeSet<PathBuf>,
+        filter: &mut Filter,
+        own_writes: &mut HashMap<PathBuf, u64>,
+    ) -> Result<Option<Report>, Error> {
+        for path in &changed {
+            filter.reload(path);
+        }
+
+        let changed: BTreeSet<_> = changed
+            .into_iter()
+            .filter(|path| path.is_file())
+            .filter(|path| {
+                let written = own_writes.get(path);
+                written.is_none() || written != content_hash(path).as_ref()
+            })
+            .filter(|path| filter.matches(path))
+            .collect();
+
+        let files: Vec<File> = match self.git_selection()? {
+            Some(selection) => self
+                .git_files(&selection)?
+                .into_iter()
+                .filter(|file| {
+                    fs::canonicalize(file.path()).is_ok_and(|path| changed.contains(&path))
+                })
+                .collect(),
+            None => {
+                let current_dir = env::current_dir()?;
+                changed
+                    .iter()
+                    .map(|path| File::new(path.strip_prefix(&current_dir).unwrap_or(path)))
+                    .collect()
+            }
+        };
+
+        // A single unreadable file must not stop the watch
+        let files: Vec<File> = files
+            .into_iter()
+            .filter(|file| match file.read() {
+                Ok(_) => true,
+                Err(err) => {
+                    eprintln!("{}: {}", file, err);
+                    false
+                }
+            })
+            .collect();
+
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        let report = if self.config.check {
+            Report::Check(self.check(files.into_iter())?)
+        } else {
+            let report = self.fix(files.into_iter())?;
+
+            for file in &report.sorted_files {
+                let path = fs::canonicalize(file.path())?;
+                if let Some(hash) = content_hash(&path) {
+                    own_writes.insert(path, hash);
+                }
+            }
+
+            Report::Fix(report)
+        };
+
+        Ok(Some(report))
+    }
+
+    /// Adds the files created or modified by an event to `changed`.
+    fn collect_changes(event: Event, changed: &mut BTreeSet<PathBuf>) {
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+
+        changed.extend(
+            event
+                .paths
+                .iter()
+                .filter_map(|path| fs::canonicalize(path).ok())
+                .filter(|path| path.is_file()),
+        );
+    }
+}
+
+/// Hashes the content of a file.
+fn content_hash(path: &Path) -> Option<u64> {
+    let mut hasher = StableHasher::default();
+    fs::read(path).ok()?.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Tells whether a file would be processed by a normal run, without walking the tree.
+///
+/// It follows the rules of the walk of [App::files]: a file must match the globs, and be under
+/// directories which are neither hidden nor ignored by the `.gitignore` and `.ignore` files.
+struct Filter {
+    /// The canonical [Config::path](crate::Config::path).
+    root: PathBuf,
+    overrides: Override,
+    /// The root of the git repository containing [Filter::root], whose `.gitignore` files apply.
+    repository: Option<PathBuf>,
+    global: Gitignore,
+    /// The ignore files read so far, by directory.
+    ignores: HashMap<PathBuf, Gitignore>,
+}
+
+impl Filter {
+    fn new(root: &str, overrides: Override) -> Result<Self, Error> {
+        let root = fs::canonicalize(root)?;
+        let repository = root
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .map(Path::to_owned);
+        let global = match repository {
+            Some(_) => Gitignore::global().0,
+            None => Gitignore::empty(),
+        };
+
+        Ok(Self {
+            root,
+            overrides,
+            repository,
+            global,
+            ignores: HashMap::new(),
+        })
+    }
+
+    /// Forgets the ignore files of the directory of `path`, if it is one of them.
+    fn reload(&mut self, path: &Path) {
+        let is_ignore_file = path
+            .file_name()
+            .is_some_and(|name| IGNORE_FILES.iter().any(|file| name == *file));
+
+        if let (true, Some(dir)) = (is_ignore_file, path.parent()) {
+            self.ignores.remove(dir);
+        }
+    }
+
+    /// Tells whether the canonical `path` would be processed.
+    fn matches(&mut self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        // Config::path is the file itself
+        if relative.as_os_str().is_empty() {
+            return true;
+        }
+
+        let file_matches = match self.overrides.matched(relative, false) {
+            Match::Whitelist(_) => true,
+            Match::Ignore(_) => false,
+            Match::None => !is_hidden(path) && !self.is_ignored(path, false),
+        };
+
+        let root = self.root.clone();
+
+        file_matches
+            && path
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| *dir != root)
+                .all(|dir| !is_hidden(dir) && !self.is_ignored(dir, true))
+    }
+
+    /// Tells whether `path` is ignored by the ignore files of its parent directories, the
+    /// closest one winning.
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        for dir in path.ancestors().skip(1) {
+            match self.ignores(dir).matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+
+            if self.repository.as_deref() == Some(dir) {
+                break;
+            }
+        }
+
+        self.global.matched(path, is_dir).is_ignore()
+    }
+
+    /// Returns the ignore files of `dir`, `.gitignore` ones only applying in a git repository.
+    fn ignores(&mut self, dir: &Path) -> &Gitignore {
+        let in_repository = self
+            .repository
+            .as_deref()
+            .is_some_and(|repository| dir.starts_with(repository));
+
+        self.ignores.entry(dir.to_owned()).or_insert_with(|| {
+            let mut builder = GitignoreBuilder::new(dir);
+            if in_repository {
+                builder.add(dir.join(".git/info/exclude"));
+            }
+            for name in IGNORE_FILES {
+                if name != ".gitignore" || in_repository {
+                    builder.add(dir.join(name));
+                }
+            }
+
+            builder.build().unwrap_or_else(|_| Gitignore::empty())
+        })
+    }
+}
+
+/// Tells whether a file or directory is hidden, its name starting with a dot.
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Config;
+
+    use super::*;
+
+    /// Creates a project, with a file for each of `paths`.
+    fn project(paths: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(
+            dir.path().join(".gitignore"),
+            "dist/\nignored.js\nsrc/gen/*.js\n",
+        )
+        .unwrap();
+
+        for path in paths {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "import b from 'b';\nimport a from 'a';\n").unwrap();
+        }
+
+        dir
+    }
+
+    fn app(dir: &Path, check: bool) -> App {
+        App::new(Config {
+            path: dir.to_string_lossy().into_owned(),
+            check,
+            no_cache: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn filter_as_files() {
+        let paths = [
+            "index.js",
+            ".hidden.js",
+            "ignored.js",
+            "readme.txt",
+            "dist/index.js",
+            ".hidden/index.js",
+            "src/index.ts",
+            "src/gen/index.js",
+            "src/lib/.ignore",
+            "src/lib/index.js",
+            "src/lib/skipped.js",
+        ];
+        let dir = project(&paths);
+        fs::write(dir.path().join("src/lib/.ignore"), "skipped.js\n").unwrap();
+        let app = app(dir.path(), true);
+
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let mut filter = Filter::new(&app.config.path, app.overrides().unwrap()).unwrap();
+        let files: BTreeSet<_> = app
+            .files()
+            .unwrap()
+            .map(|file| fs::canonicalize(file.path()).unwrap())
+            .collect();
+
+        assert_eq!(files.len(), 7);
+        for path in paths {
+            let path = root.join(path);
+            assert_eq!(filter.matches(&path), files.contains(&path), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn reload_ignore_files() {
+        let dir = project(&["src/index.js"]);
+        let app = app(dir.path(), true);
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let mut filter = Filter::new(&app.config.path, app.overrides().unwrap()).unwrap();
+
+        assert!(filter.matches(&root.join("src/index.js")));
+
+        fs::write(root.join(".gitignore"), "src/\n").unwrap();
+        filter.reload(&root.join(".gitignore"));
+
+        assert!(!filter.matches(&root.join("src/index.js")));
+    }
+
+    #[test]
+    fn own_writes() {
+        let dir = project(&["index.js", "dist/index.js"]);
+        let app = app(dir.path(), false);
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let mut filter = Filter::new(&app.config.path, app.overrides().unwrap()).unwrap();
+        let mut own_writes = HashMap::new();
+        let changed = || BTreeSet::from([root.join("index.js"), root.join("dist/index.js")]);
+
+        let Some(Report::Fix(report)) = app
+            .process(changed(), &mut filter, &mut own_writes)
+            .unwrap()
+        else {
+            panic!("expected a fix report");
+        };
+        assert_eq!(report.sorted_files.len(), 1);
+        assert_eq!(
+            fs::read_to_string(root.join("index.js")).unwrap(),
+            "import a from 'a';\nimport b from 'b';\n"
+        );
+
+        // The change made by jisort itself
+        let report = app.process(changed(), &mut filter, &mut own_writes);
+        assert!(report.unwrap().is_none());
+
+        // A change made right after, by the user
+        fs::write(
+            root.join("index.js"),
+            "import c from 'c';\nimport a from 'a';\n",
+        )
+        .unwrap();
+        let Some(Report::Fix(report)) = app
+            .process(changed(), &mut filter, &mut own_writes)
+            .unwrap()
+        else {
+            panic!("expected a fix report");
+        };
+        assert_eq!(report.sorted_files.len(), 1);
+    }
+
+    #[test]
+    fn removed_and_unreadable_files() {
+        let dir = project(&["index.js", "removed.js", "binary.js"]);
+        let app = app(dir.path(), true);
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let mut filter = Filter::new(&app.config.path, app.overrides().unwrap()).unwrap();
+        let changed = BTreeSet::from([
+            root.join("index.js"),
+            root.join("removed.js"),
+            root.join("binary.js"),
+        ]);
+
+        fs::remove_file(root.join("removed.js")).unwrap();
+        fs::write(root.join("binary.js"), b"import a from '\xff';\n").unwrap();
+
+        let Some(Report::Check(report)) = app
+            .process(changed, &mut filter, &mut HashMap::new())
+            .unwrap()
+        else {
+            panic!("expected a check report");
+        };
+        assert_eq!(report.errored_files.len(), 1);
+        assert!(report.errored_files[0].path().ends_with("index.js"));
+    }
+}
@@ -0,0 +1,473 @@
+//! On-disk cache of the previous run results.
+//!
+//! It allows skipping the parsing of files which haven't changed since the last run.
+//!
+//! Entries are keyed by path & content hash, and only hold while the files looked for to classify
+//! their imports are still found, see [Probes].
+//! The whole cache is invalidated when the jisort version or the [Config] sort options change.
+
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use tempfile::NamedTempFile;
+
+use crate::{
+    app::VERSION,
+    file::{File, FileData},
+    sorter::Sorter,
+    Config, Error,
+};
+
+/// Default name of the cache file.
+pub const DEFAULT_LOCATION: &str = ".jisort-cache";
+
+/// What is known about a file content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    /// Imports are sorted, see [File::check].
+    pub sorted: bool,
+    /// Sorting is dangerous, see [FileData::is_dangerous].
+    pub dangerous: bool,
+    /// Sorting would not change the file content.
+    pub formatted: bool,
+    /// Imports are found after other code, see [FileData::has_scattered].
+    pub scattered: bool,
+    /// Some imports are not understood, see [FileData::diagnostics].
+    pub diagnosed: bool,
+}
+
+impl Status {
+    /// Computes the status of a parsed file.
+    pub fn new(file: &File, data: &FileData) -> Self {
+        Self {
+            sorted: file.check(data),
+            dangerous: data.is_dangerous(),
+            formatted: File::render(data) == data.source,
+            scattered: data.has_scattered(),
+            diagnosed: data.diagnostics().next().is_some(),
+        }
+    }
+
+    fn encode(&self) -> String {
+        [
+            if self.sorted { 's' } else { '-' },
+            if self.dangerous { 'd' } else { '-' },
+            if self.formatted { 'f' } else { '-' },
+            if self.scattered { 'x' } else { '-' },
+            if self.diagnosed { 'w' } else { '-' },
+        ]
+        .iter()
+        .collect()
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let flag = |i: usize, c: u8| match value.as_bytes().get(i) {
+            Some(b'-') => Some(false),
+            Some(f) if *f == c => Some(true),
+            _ => None,
+        };
+
+        if value.len() != 5 {
+            return None;
+        }
+
+        Some(Self {
+            sorted: flag(0, b's')?,
+            dangerous: flag(1, b'd')?,
+            formatted: flag(2, b'f')?,
+            scattered: flag(3, b'x')?,
+            diagnosed: flag(4, b'w')?,
+        })
+    }
+}
+
+/// The modules of a file which are looked for on the filesystem to be classified, and whether
+/// they are found, see [Sorter::probe].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Probes(Vec<(String, bool)>);
+
+impl Probes {
+    /// Collects the probes of the imports of a parsed file.
+    pub fn new(data: &FileData) -> Self {
+        let mut probes: Vec<_> = data
+            .scripts()
+            .flat_map(|script| {
+                script
+                    .imports
+                    .iter()
+                    .chain(script.scattered.iter().map(|scattered| &scattered.import))
+                    .filter_map(|import| {
+                        let found = script.sorter.probe(&import.module)?;
+                        Some((import.module.clone(), found))
+                    })
+            })
+            .collect();
+        probes.sort();
+        probes.dedup();
+
+        Self(probes)
+    }
+
+    /// Tells whether the same modules are found with `sorter`.
+    fn hold(&self, sorter: &Sorter) -> bool {
+        self.0
+            .iter()
+            .all(|(module, found)| sorter.probe(module) == Some(*found))
+    }
+
+    /// Encodes the probes, unless a module contains a whitespace.
+    fn encode(&self) -> Option<String> {
+        let mut probes = Vec::with_capacity(self.0.len());
+
+        for (module, found) in &self.0 {
+            if module.is_empty() || module.contains(char::is_whitespace) {
+                return None;
+            }
+            probes.push(format!("{}{}", if *found { '+' } else { '-' }, module));
+        }
+
+        Some(probes.join(" "))
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        value
+            .split_whitespace()
+            .map(|probe| match probe.split_at(1) {
+                ("+", module) if !module.is_empty() => Some((module.to_owned(), true)),
+                ("-", module) if !module.is_empty() => Some((module.to_owned(), false)),
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+}
+
+/// The results of the previous runs.
+#[derive(Debug)]
+pub struct Cache {
+    location: PathBuf,
+    /// Identifies the jisort version & options the entries were computed with.
+    header: String,
+    entries: HashMap<PathBuf, (u64, Status, Probes)>,
+    dirty: bool,
+}
+
+impl Cache {
+    /// Loads the cache from `location`.
+    ///
+    /// A missing, unreadable or outdated cache is considered empty.
+    pub fn load<P>(location: P, config: &Config) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        let mut cache = Self {
+            location: location.into(),
+            header: format!(
+                "jisort-cache {} {:016x}",
+                VERSION,
+                config.sort_options_hash()
+            ),
+            entries: HashMap::new(),
+            dirty: false,
+        };
+
+        let Ok(content) = fs::read_to_string(&cache.location) else {
+            return cache;
+        };
+        let mut lines = content.lines();
+
+        if lines.next() != Some(cache.header.as_str()) {
+            cache.dirty = true;
+            return cache;
+        }
+
+        for line in lines {
+            let mut fields = line.splitn(4, '\t');
+            let (Some(status), Some(hash), Some(probes), Some(path)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            if let (Some(status), Ok(hash), Some(probes)) = (
+                Status::decode(status),
+                u64::from_str_radix(hash, 16),
+                Probes::decode(probes),
+            ) {
+                cache
+                    .entries
+                    .insert(PathBuf::from(path), (hash, status, probes));
+            }
+        }
+
+        cache
+    }
+
+    /// Returns the recorded status of a file, if its content hasn't changed and the same modules
+    /// are found with `sorter`.
+    pub fn get(&self, path: &Path, hash: u64, sorter: &Sorter) -> Option<Status> {
+        let path = fs::canonicalize(path).ok()?;
+
+        match self.entries.get(&path) {
+            Some((cached, status, probes)) if *cached == hash && probes.hold(sorter) => {
+                Some(*status)
+            }
+            _ => None,
+        }
+    }
+
+    /// Records the status of a file.
+    ///
+    /// Files which probes can't be written are not recorded.
+    pub fn insert(&mut self, path: &Path, hash: u64, status: Status, probes: Probes) {
+        if probes.encode().is_none() {
+            return;
+        }
+
+        if let Ok(path) = fs::canonicalize(path) {
+            let entry = (hash, status, probes);
+            if self.entries.get(&path) != Some(&entry) {
+                self.entries.insert(path, entry);
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Writes the cache back to its location, if it changed, creating its directory if needed.
+    pub fn save(&mut self) -> Result<(), Error> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let mut content = self.header.clone();
+        content.push('\n');
+
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|(path, _)| *path);
+
+        for (path, (hash, status, probes)) in entries {
+            content.push_str(&format!(
+                "{}\t{:016x}\t{}\t{}\n",
+                status.encode(),
+                hash,
+                probes.encode().unwrap_or_default(),
+                path.display()
+            ));
+        }
+
+        let dir = match self.location.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::create_dir_all(dir)?;
+        let mut tmp = NamedTempFile::new_in(dir)?;
+        tmp.write_all(content.as_bytes())?;
+        tmp.persist(&self.location).map_err(|e| e.error)?;
+
+        self.dirty = false;
+
+        Ok(())
+    }
+}
+
+/// Hashes a file content and the settings it is sorted with.
+pub fn hash(content: &str, sorter: &Sorter) -> u64 {
+    let mut hasher = StableHasher::default();
+    content.hash(&mut hasher);
+    sorter.fingerprint().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The 64 bits FNV-1a hash function.
+///
+/// Unlike [DefaultHasher](std::collections::hash_map::DefaultHasher), its hashes are the same
+/// across Rust releases, so they can be written to the cache.
+#[derive(Debug)]
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file::Format, project::Project};
+
+    fn setup() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("index.js");
+        fs::write(&file, "import a from 'a';\n").unwrap();
+        let location = dir.path().join(DEFAULT_LOCATION);
+
+        (dir, file, location)
+    }
+
+    const STATUS: Status = Status {
+        sorted: true,
+        dangerous: false,
+        formatted: true,
+        scattered: false,
+        diagnosed: false,
+    };
+
+    #[test]
+    fn status_encoding() {
+        for sorted in [true, false] {
+            for dangerous in [true, false] {
+                for formatted in [true, false] {
+                    for scattered in [true, false] {
+                        for diagnosed in [true, false] {
+                            let status = Status {
+                                sorted,
+                                dangerous,
+                                formatted,
+                                scattered,
+                                diagnosed,
+                            };
+                            assert_eq!(Status::decode(&status.encode()), Some(status));
+                        }
+                    }
+                }
+            }
+        }
+
+        assert_eq!(Status::decode("sdf"), None);
+        assert_eq!(Status::decode("sdfx"), None);
+        assert_eq!(Status::decode("sdfxy"), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let (_dir, file, location) = setup();
+        let config = Config::default();
+
+        let sorter = Sorter::default();
+
+        let mut cache = Cache::load(&location, &config);
+        assert_eq!(cache.get(&file, 1, &sorter), None);
+        cache.insert(&file, 1, STATUS, Probes::default());
+        cache.save().unwrap();
+
+        let cache = Cache::load(&location, &config);
+        assert_eq!(cache.get(&file, 1, &sorter), Some(STATUS));
+    }
+
+    #[test]
+    fn content_changed() {
+        let (_dir, file, location) = setup();
+        let config = Config::default();
+
+        let mut cache = Cache::load(&location, &config);
+        cache.insert(&file, 1, STATUS, Probes::default());
+        cache.save().unwrap();
+
+        let cache = Cache::load(&location, &config);
+        assert_eq!(cache.get(&file, 2, &Sorter::default()), None);
+    }
+
+    #[test]
+    fn config_changed() {
+        let (_dir, file, location) = setup();
+
+        let mut cache = Cache::load(&location, &Config::default());
+        cache.insert(&file, 1, STATUS, Probes::default());
+        cache.save().unwrap();
+
+        let config = Config {
+            globs: vec!["**.ts".to_owned()],
+            ..Default::default()
+        };
+        let cache = Cache::load(&location, &config);
+        assert_eq!(cache.get(&file, 1, &Sorter::default()), None);
+    }
+
+    #[test]
+    fn stable_hash() {
+        let mut hasher = StableHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn probes_encoding() {
+        let probes = Probes(vec![
+            ("components".to_owned(), true),
+            ("zod".to_owned(), false),
+        ]);
+
+        assert_eq!(probes.encode().as_deref(), Some("+components -zod"));
+        assert_eq!(Probes::decode("+components -zod"), Some(probes));
+        assert_eq!(Probes::decode(""), Some(Probes::default()));
+        assert_eq!(Probes::decode("components"), None);
+        assert_eq!(Probes(vec![("a b".to_owned(), true)]).encode(), None);
+    }
+
+    #[test]
+    fn base_url_files_changed() {
+        let (dir, file, location) = setup();
+        let config = Config::default();
+        fs::write(&file, "import utils from 'utils';\n").unwrap();
+        fs::write(
+            dir.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": "." } }"#,
+        )
+        .unwrap();
+
+        let sorter = Sorter::new(&config, Project::for_file(&file));
+        let source = fs::read_to_string(&file).unwrap();
+        let data = File::parse_with(&source, Format::default(), sorter.clone());
+        let hash = hash(&source, &sorter);
+
+        let mut cache = Cache::load(&location, &config);
+        cache.insert(&file, hash, STATUS, Probes::new(&data));
+        cache.save().unwrap();
+        let cache = Cache::load(&location, &config);
+        assert_eq!(cache.get(&file, hash, &sorter), Some(STATUS));
+
+        // `utils` is now an alias
+        fs::write(dir.path().join("utils.ts"), "").unwrap();
+        assert_eq!(cache.get(&file, hash, &sorter), None);
+    }
+
+    #[test]
+    fn workspace_packages_changed() {
+        let (dir, file, _) = setup();
+        let config = Config::default();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{ "workspaces": ["packages/*"] }"#,
+        )
+        .unwrap();
+        let sorter = Sorter::new(&config, Project::for_file(&file));
+
+        fs::create_dir_all(dir.path().join("packages/a")).unwrap();
+        fs::write(
+            dir.path().join("packages/a/package.json"),
+            r#"{ "name": "a" }"#,
+        )
+        .unwrap();
+
+        let updated = Sorter::new(&config, Project::for_file(&file));
+        assert_ne!(sorter.fingerprint(), updated.fingerprint());
+    }
+}
@@ -1,8 +1,18 @@
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 #[cfg(feature = "argh")]
 use argh::FromArgs;
 
+use crate::{
+    cache::StableHasher,
+    statements::{Extensions, ImportType, ASSET_EXTENSIONS, STYLE_EXTENSIONS},
+};
+
 /// A JavaScript / Typescript import sorter specifically built for JVS.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "argh", derive(FromArgs))]
@@ -19,23 +29,158 @@ pub struct Config {
     /// Multiple globs can be separated by a comma `,`.
     #[cfg_attr(
         feature = "argh",
-        argh(option, default = "globs_default()", from_str_fn(from_str_globs))
+        argh(option, default = "globs_default()", from_str_fn(from_str_list))
     )]
     pub globs: Vec<String>,
 
+    /// only process files with staged or unstaged changes, and untracked files
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub changed: bool,
+
+    /// only process staged files, reading their staged content. Requires `--check`
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub staged: bool,
+
+    /// only process files that differ from the given git revision
+    #[cfg_attr(feature = "argh", argh(option))]
+    pub since: Option<String>,
+
+    /// keep running, checking or sorting files as they change.
+    /// Only available on the `watch` feature
+    #[cfg(feature = "watch")]
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub watch: bool,
+
     /// list included files, then stops
     #[cfg_attr(feature = "argh", argh(switch))]
     pub list: bool,
 
+    /// explain how a file is sorted, then stops
+    #[cfg_attr(feature = "argh", argh(option))]
+    pub explain: Option<String>,
+
+    /// explain every badly sorted or dangerous file in the report
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub verbose: bool,
+
+    /// how to write Node.js built-in modules: `keep` them as is (default),
+    /// `add` the `node:` prefix or `remove` it
+    #[cfg_attr(feature = "argh", argh(option, default = "NodePrefix::Keep"))]
+    pub node_prefix: NodePrefix,
+
+    /// how to compare modules of the same group: `bytewise` (default), `case-insensitive`,
+    /// `natural` (case-insensitive with numbers compared by value) or `eslint`
+    /// (as eslint-plugin-import `alphabetize`)
+    #[cfg_attr(feature = "argh", argh(option, default = "ModuleOrder::Bytewise"))]
+    pub module_order: ModuleOrder,
+
+    /// how to order relative imports: `lexical` (default), `farthest-first` (parents
+    /// farthest first, then siblings, then `./index`) or `closest-first` (the reverse)
+    #[cfg_attr(feature = "argh", argh(option, default = "RelativeOrder::Lexical"))]
+    pub relative_order: RelativeOrder,
+
+    /// number of blank lines between import groups. Defaults to 1
+    #[cfg_attr(feature = "argh", argh(option, default = "1"))]
+    pub group_blank_lines: usize,
+
+    /// write a header comment before each import group, like `// Modules`
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub group_headers: bool,
+
+    /// text of the header of a group, as `<group>=<text>` like `module=External`.
+    /// Can be repeated. Groups are `builtin`, `global`, `scoped-module`, `module`, `workspace`,
+    /// `alias`, `local`, `style-module`, `style` and `asset`
+    #[cfg_attr(feature = "argh", argh(option, from_str_fn(from_str_group_header)))]
+    pub group_header: Vec<String>,
+
+    /// move imports found after other code up into the sorted imports, unless they follow a
+    /// `jest.mock()` like call
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub hoist: bool,
+
+    /// also sort imports in the fenced js / ts code blocks of Markdown and MDX files,
+    /// and include `.md` files
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub code_blocks: bool,
+
+    /// extensions of stylesheets, grouped after local imports.
+    /// Multiple extensions can be separated by a comma `,`.
+    /// Defaults to `css,scss,sass,less,styl,pcss,sss`
+    #[cfg_attr(
+        feature = "argh",
+        argh(
+            option,
+            default = "style_extensions_default()",
+            from_str_fn(from_str_list)
+        )
+    )]
+    pub style_extensions: Vec<String>,
+
+    /// extensions of assets like images, fonts or media, grouped last.
+    /// Multiple extensions can be separated by a comma `,`
+    #[cfg_attr(
+        feature = "argh",
+        argh(
+            option,
+            default = "asset_extensions_default()",
+            from_str_fn(from_str_list)
+        )
+    )]
+    pub asset_extensions: Vec<String>,
+
     /// forces sorting when it is dangerous
     #[cfg_attr(feature = "argh", argh(switch))]
     pub force: bool,
 
+    /// fails when imports are not understood, instead of warning about them
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub strict: bool,
+
+    /// keep a copy of the original files before writing them
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub backup: bool,
+
+    /// suffix appended to the backup files. Defaults to `.orig`.
+    #[cfg_attr(feature = "argh", argh(option, default = "backup_suffix_default()"))]
+    pub backup_suffix: String,
+
+    /// don't read nor write the cache of previous results
+    #[cfg_attr(feature = "argh", argh(switch))]
+    pub no_cache: bool,
+
+    /// path of the cache file. Defaults to `.jisort-cache`
+    #[cfg_attr(feature = "argh", argh(option))]
+    pub cache_location: Option<String>,
+
     /// display version
     #[cfg_attr(feature = "argh", argh(switch, short = 'v'))]
     pub version: bool,
+
+    /// subcommand to run instead of sorting.
+    ///
+    /// Only available on the `lsp` feature.
+    #[cfg(feature = "lsp")]
+    #[cfg_attr(feature = "argh", argh(subcommand))]
+    pub command: Option<Command>,
+}
+
+/// The jisort subcommands.
+#[cfg(feature = "lsp")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "argh", derive(FromArgs))]
+#[cfg_attr(feature = "argh", argh(subcommand))]
+pub enum Command {
+    /// Runs the language server, see [crate::lsp].
+    Lsp(LspCommand),
 }
 
+/// start a Language Server Protocol server over stdio
+#[cfg(feature = "lsp")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "argh", derive(FromArgs))]
+#[cfg_attr(feature = "argh", argh(subcommand, name = "lsp"))]
+pub struct LspCommand {}
+
 impl Config {
     /// Load config from cli arguments.
     ///
@@ -49,6 +194,58 @@ impl Config {
     pub fn is_path_file(&self) -> bool {
         Path::new(&self.path).is_file()
     }
+
+    /// Location of the cache file, [None] if the cache is disabled.
+    pub fn cache_location(&self) -> Option<PathBuf> {
+        if self.no_cache {
+            return None;
+        }
+
+        Some(PathBuf::from(
+            self.cache_location
+                .as_deref()
+                .unwrap_or(crate::cache::DEFAULT_LOCATION),
+        ))
+    }
+
+    /// Extensions recognizing stylesheet and asset imports.
+    pub fn extensions(&self) -> Extensions {
+        Extensions {
+            styles: self.style_extensions.clone(),
+            assets: self.asset_extensions.clone(),
+        }
+    }
+
+    /// Texts of the group headers given with [Config::group_header], by group.
+    ///
+    /// Invalid entries are ignored, they are rejected on the command line.
+    pub(crate) fn group_header_texts(&self) -> BTreeMap<ImportType, String> {
+        self.group_header
+            .iter()
+            .filter_map(|header| parse_group_header(header).ok())
+            .map(|(ty, text)| (ty, text.to_owned()))
+            .collect()
+    }
+
+    /// Hash of the options changing the result of a run, used to invalidate the cache.
+    ///
+    /// Every option changing how files are selected or sorted must be hashed here.
+    pub(crate) fn sort_options_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+
+        self.globs.hash(&mut hasher);
+        self.node_prefix.hash(&mut hasher);
+        self.module_order.hash(&mut hasher);
+        self.relative_order.hash(&mut hasher);
+        self.group_blank_lines.hash(&mut hasher);
+        self.group_headers.hash(&mut hasher);
+        self.group_header_texts().hash(&mut hasher);
+        self.hoist.hash(&mut hasher);
+        self.code_blocks.hash(&mut hasher);
+        self.extensions().hash(&mut hasher);
+
+        hasher.finish()
+    }
 }
 
 impl Default for Config {
@@ -57,9 +254,124 @@ impl Default for Config {
             path: ".".to_owned(),
             check: false,
             globs: globs_default(),
+            changed: false,
+            staged: false,
+            since: None,
+            #[cfg(feature = "watch")]
+            watch: false,
             list: false,
+            explain: None,
+            verbose: false,
+            node_prefix: NodePrefix::Keep,
+            module_order: ModuleOrder::Bytewise,
+            relative_order: RelativeOrder::Lexical,
+            group_blank_lines: 1,
+            group_headers: false,
+            group_header: Vec::new(),
+            hoist: false,
+            code_blocks: false,
+            style_extensions: style_extensions_default(),
+            asset_extensions: asset_extensions_default(),
             force: false,
+            strict: false,
+            backup: false,
+            backup_suffix: backup_suffix_default(),
+            no_cache: false,
+            cache_location: None,
             version: false,
+            #[cfg(feature = "lsp")]
+            command: None,
+        }
+    }
+}
+
+/// How to write the `node:` prefix of Node.js built-in modules.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodePrefix {
+    /// Keep built-in modules as they are written.
+    #[default]
+    Keep,
+    /// Write built-in modules with the `node:` prefix, like `node:fs`.
+    Add,
+    /// Write built-in modules without the `node:` prefix, like `fs`.
+    ///
+    /// Modules that only exist with the prefix, like `node:test`, keep it.
+    Remove,
+}
+
+impl FromStr for NodePrefix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Self::Keep),
+            "add" => Ok(Self::Add),
+            "remove" => Ok(Self::Remove),
+            _ => Err(format!(
+                "invalid node prefix `{}`, expected `keep`, `add` or `remove`",
+                s
+            )),
+        }
+    }
+}
+
+/// How to compare the modules of imports in the same group.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleOrder {
+    /// Compares the bytes of modules, so `Zod` comes before `axios` and `file10` before `file2`.
+    #[default]
+    Bytewise,
+    /// Compares modules ignoring case, like `axios` before `Zod`.
+    CaseInsensitive,
+    /// Compares modules ignoring case, and numbers by value, like `file2` before `file10`.
+    Natural,
+    /// Compares modules as eslint-plugin-import `import/order` with `alphabetize: { order: 'asc' }`.
+    ///
+    /// Modules are compared segment by segment, split on `/`, so `.` comes before `..`.
+    Eslint,
+}
+
+impl FromStr for ModuleOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bytewise" => Ok(Self::Bytewise),
+            "case-insensitive" => Ok(Self::CaseInsensitive),
+            "natural" => Ok(Self::Natural),
+            "eslint" => Ok(Self::Eslint),
+            _ => Err(format!(
+                "invalid module order `{}`, expected `bytewise`, `case-insensitive`, `natural` or `eslint`",
+                s
+            )),
+        }
+    }
+}
+
+/// How to order relative imports, like `../utils` or `./Button`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelativeOrder {
+    /// Only compares modules, see [ModuleOrder].
+    #[default]
+    Lexical,
+    /// Parents first, farthest first, then siblings, then the index (`.` or `./index`).
+    FarthestFirst,
+    /// The index (`.` or `./index`) first, then siblings, then parents, closest first.
+    ClosestFirst,
+}
+
+impl FromStr for RelativeOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lexical" => Ok(Self::Lexical),
+            "farthest-first" => Ok(Self::FarthestFirst),
+            "closest-first" => Ok(Self::ClosestFirst),
+            _ => Err(format!(
+                "invalid relative order `{}`, expected `lexical`, `farthest-first` or `closest-first`",
+                s
+            )),
         }
     }
 }
@@ -71,10 +383,61 @@ pub fn globs_default() -> Vec<String> {
         "**.jsx".to_owned(),
         "**.ts".to_owned(),
         "**.tsx".to_owned(),
+        "**.vue".to_owned(),
+        "**.svelte".to_owned(),
+        "**.astro".to_owned(),
+        "**.mdx".to_owned(),
+        "**.js.flow".to_owned(),
     ]
 }
 
+/// Returns the default extensions of stylesheets, see [STYLE_EXTENSIONS].
+pub fn style_extensions_default() -> Vec<String> {
+    STYLE_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+/// Returns the default extensions of assets, see [ASSET_EXTENSIONS].
+pub fn asset_extensions_default() -> Vec<String> {
+    ASSET_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+/// Returns the default suffix of backup files.
+pub fn backup_suffix_default() -> String {
+    ".orig".to_owned()
+}
+
+/// Parses a `<group>=<text>` group header, see [Config::group_header].
+fn parse_group_header(value: &str) -> Result<(ImportType, &str), String> {
+    let (key, text) = value.split_once('=').ok_or_else(|| {
+        format!(
+            "invalid group header `{}`, expected `<group>=<text>`",
+            value
+        )
+    })?;
+    let ty = ImportType::from_key(key.trim()).ok_or_else(|| {
+        let keys: Vec<_> = ImportType::ALL.iter().map(|ty| ty.key()).collect();
+        format!(
+            "invalid group `{}`, expected one of `{}`",
+            key,
+            keys.join("`, `")
+        )
+    })?;
+
+    match text.trim() {
+        "" => Err(format!("empty text for the `{}` group header", key)),
+        text if text.contains(['\n', '\r']) => {
+            Err(format!("the `{}` group header must fit on one line", key))
+        }
+        text => Ok((ty, text)),
+    }
+}
+
+#[cfg(feature = "argh")]
+fn from_str_group_header(value: &str) -> Result<String, String> {
+    parse_group_header(value).map(|_| value.to_owned())
+}
+
 #[cfg(feature = "argh")]
-fn from_str_globs(value: &str) -> Result<Vec<String>, String> {
+fn from_str_list(value: &str) -> Result<Vec<String>, String> {
     Ok(value.split(',').map(String::from).collect())
 }
@@ -11,12 +11,26 @@ pub enum Error {
     Ignore(ignore::Error),
     /// Errors from [std::io::Error].
     Io(io::Error),
+    /// Errors from the [notify] library.
+    #[cfg(feature = "watch")]
+    Notify(notify::Error),
+    /// Errors from the [lsp_server] library.
+    #[cfg(feature = "lsp")]
+    Lsp(lsp_server::ProtocolError),
 
     /// Jsort generic error
     Jsort {
         /// Text describing the error
         err: String,
     },
+
+    /// The sorted content of a file did not pass the round-trip verification.
+    ///
+    /// This is an internal error, the file is left untouched.
+    Verification {
+        /// Text describing what did not survive sorting
+        err: String,
+    },
 }
 
 impl std::error::Error for Error {}
@@ -27,8 +41,15 @@ impl Display for Error {
             Self::Jsort { err } => {
                 write!(f, "\x1b[31mjisort error:\x1b[m {}", err)
             }
+            Self::Verification { err } => {
+                write!(f, "\x1b[31mjisort internal error:\x1b[m {}", err)
+            }
             Error::Ignore(err) => err.fmt(f),
             Error::Io(err) => err.fmt(f),
+            #[cfg(feature = "watch")]
+            Error::Notify(err) => err.fmt(f),
+            #[cfg(feature = "lsp")]
+            Error::Lsp(err) => err.fmt(f),
         }
     }
 }
@@ -52,3 +73,17 @@ impl From<std::io::Error> for Error {
         Self::Io(value)
     }
 }
+
+#[cfg(feature = "watch")]
+impl From<notify::Error> for Error {
+    fn from(value: notify::Error) -> Self {
+        Self::Notify(value)
+    }
+}
+
+#[cfg(feature = "lsp")]
+impl From<lsp_server::ProtocolError> for Error {
+    fn from(value: lsp_server::ProtocolError) -> Self {
+        Self::Lsp(value)
+    }
+}
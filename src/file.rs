@@ -3,21 +3,61 @@
 //! [File] is the main datatype of jisort.
 //! It enabled [fixing](File::fix) & [checking](File::check) a file.
 
+mod diagnostic;
 mod filedata;
-use std::{fmt::Display, fs, path::PathBuf};
+mod format;
+mod lexer;
+mod line_ending;
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    fmt::Display,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use tempfile::NamedTempFile;
 
 use crate::{
-    parser::{comment, empty_line, import_statement},
-    statements::{ImportType, Statement},
-    Error,
+    git,
+    parser::{comment, empty_line, import_statement, namespace_alias, triple_slash_directive},
+    project::Project,
+    sorter::Sorter,
+    statements::{ImportType, Statement, TypeOnly},
+    Config, Error,
+};
+
+pub use self::{
+    diagnostic::ParseDiagnostic, filedata::FileData, format::Format, line_ending::LineEnding,
+};
+use self::{
+    filedata::{Block, ScatteredImport},
+    lexer::Lexer,
 };
 
-pub use self::filedata::FileData;
+/// Calls which must stay before the imports following them, as they are hoisted above imports
+/// by test runners.
+const MOCK_CALLS: [&str; 8] = [
+    "jest.mock(",
+    "jest.unmock(",
+    "jest.doMock(",
+    "jest.dontMock(",
+    "vi.mock(",
+    "vi.unmock(",
+    "vi.doMock(",
+    "vi.hoisted(",
+];
+
+/// The UTF-8 byte order mark.
+const BOM: char = '\u{feff}';
 
 /// Represents a file that can be parsed, fixed or checked.
 #[derive(Debug, Clone)]
 pub struct File {
     path: PathBuf,
+    /// Whether the content is read from the git index rather than from the working tree.
+    staged: bool,
 }
 
 impl File {
@@ -26,46 +66,257 @@ impl File {
     where
         P: Into<PathBuf>,
     {
-        Self { path: path.into() }
+        Self {
+            path: path.into(),
+            staged: false,
+        }
+    }
+
+    /// Create a new [File] which content is read from the git index.
+    ///
+    /// Such a file can be checked, but not fixed.
+    pub fn staged<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            path: path.into(),
+            staged: true,
+        }
     }
 
     /// Fix the file import order.
     ///
     /// This does **not** take into account [Config::force](crate::Config::force), in other words
     /// this will also format dangerous files.
-    pub fn fix(&self, data: &FileData) -> Result<(), Error> {
-        let mut imports = data.imports.clone();
+    ///
+    /// The sorted content is [verified](File::verify) before being written.
+    /// The file is written atomically, see [File::write].
+    ///
+    /// Returns whether the file has been written, files already sorted are left untouched.
+    pub fn fix(&self, data: &FileData, config: &Config) -> Result<bool, Error> {
+        if self.staged {
+            return Err("staged files can't be fixed".into());
+        }
+
+        let content = Self::render(data);
+        if content == data.source {
+            return Ok(false);
+        }
+
+        Self::verify(data, &content)?;
+
+        let backup_suffix = config.backup.then_some(config.backup_suffix.as_str());
+        self.write(&content, backup_suffix)?;
+
+        Ok(true)
+    }
+
+    /// Verifies that sorting did not lose anything.
+    ///
+    /// The sorted `content` is parsed again and compared to the original `data`:
+    ///
+    /// - the imports (module and identifiers) must be the same, in any order
+    /// - the comments must be the same, in any order
+    /// - the rest of the file must be the same, byte for byte
+    /// - sorting `content` again must not change it
+    pub(crate) fn verify(data: &FileData, content: &str) -> Result<(), Error> {
+        let sorted = Self::parse_with(content, data.format, data.sorter.clone());
+
+        let err = if Self::import_set(data) != Self::import_set(&sorted) {
+            Some("imports have changed")
+        } else if Self::comment_set(data) != Self::comment_set(&sorted) {
+            Some("comments have changed")
+        } else if !data
+            .scripts()
+            .map(|s| s.kept_rest())
+            .eq(sorted.scripts().map(|s| s.kept_rest()))
+        {
+            Some("code after imports has changed")
+        } else if !Self::outside_blocks(data).eq(Self::outside_blocks(&sorted)) {
+            Some("code around scripts has changed")
+        } else if Self::render(&sorted) != content {
+            Some("sorting is not idempotent")
+        } else {
+            None
+        };
+
+        match err {
+            Some(err) => Err(Error::Verification {
+                err: err.to_owned(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the sorted list of imports modules, type-only keywords & identifiers.
+    ///
+    /// Modules are compared as they should be written, see [Sorter::module].
+    fn import_set(data: &FileData) -> Vec<(Cow<'_, str>, Option<TypeOnly>, Option<&str>)> {
+        let mut imports: Vec<_> = data
+            .scripts()
+            .flat_map(|script| {
+                script
+                    .imports
+                    .iter()
+                    .chain(script.scattered.iter().map(|s| &s.import))
+                    .map(|i| {
+                        (
+                            script.sorter.module(i),
+                            i.type_only,
+                            i.identifiers.as_deref(),
+                        )
+                    })
+            })
+            .collect();
         imports.sort();
+        imports
+    }
+
+    /// Returns the sorted list of every comment in the import header.
+    fn comment_set(data: &FileData) -> Vec<String> {
+        let mut comments: Vec<_> = data
+            .scripts()
+            .flat_map(|script| {
+                script
+                    .directives
+                    .iter()
+                    .map(|d| d.code())
+                    .chain(script.statements.values().flatten().map(|s| s.code()))
+                    .chain(
+                        script
+                            .imports
+                            .iter()
+                            .chain(script.scattered.iter().map(|s| &s.import))
+                            .filter_map(|i| i.comment.as_ref().map(|c| c.code())),
+                    )
+            })
+            .collect();
+        comments.sort();
+        comments
+    }
+
+    /// Replaces the file content.
+    ///
+    /// The content is written to a temporary file in the same directory, which is then renamed
+    /// over the original file. This way the file is never left half written.
+    ///
+    /// Symlinks are resolved so that the link is kept and its target is updated.
+    /// The file permissions are kept as well.
+    ///
+    /// When `backup_suffix` is given, the original file is copied alongside with this suffix.
+    fn write(&self, content: &str, backup_suffix: Option<&str>) -> Result<(), Error> {
+        let path = fs::canonicalize(&self.path)?;
+        let permissions = fs::metadata(&path)?.permissions();
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
 
-        let mut text_imports = String::new();
+        if let Some(suffix) = backup_suffix {
+            let mut backup = OsString::from(path.as_os_str());
+            backup.push(suffix);
+            fs::copy(&path, backup)?;
+        }
+
+        let mut tmp = NamedTempFile::new_in(dir)?;
+        tmp.write_all(content.as_bytes())?;
+        tmp.as_file().sync_all()?;
+        fs::set_permissions(tmp.path(), permissions)?;
+        tmp.persist(&path).map_err(|e| e.error)?;
+
+        Ok(())
+    }
+
+    /// Renders the sorted content of a file.
+    ///
+    /// The byte order mark, line ending and trailing newline of the original file are restored.
+    pub(crate) fn render(data: &FileData) -> String {
+        if !data.format.is_container() {
+            return Self::render_script(data);
+        }
+
+        let mut content = String::new();
+        let mut end = 0;
+
+        for block in &data.blocks {
+            content.push_str(&data.source[end..block.range.start]);
+            content.push_str(&format::indent(
+                &Self::render_script(&block.data),
+                &block.indent,
+            ));
+            end = block.range.end;
+        }
+
+        content + &data.source[end..]
+    }
+
+    /// Returns the text of a container file around its scripts.
+    fn outside_blocks(data: &FileData) -> impl Iterator<Item = &str> {
+        let ends = std::iter::once(0).chain(data.blocks.iter().map(|b| b.range.end));
+        let starts = data
+            .blocks
+            .iter()
+            .map(|b| b.range.start)
+            .chain(std::iter::once(data.source.len()));
+
+        ends.zip(starts)
+            .filter(|_| data.format.is_container())
+            .map(|(start, end)| &data.source[start..end])
+    }
+
+    /// Renders a script with its sorted imports.
+    fn render_script(data: &FileData) -> String {
+        let mut imports = data.imports.clone();
+        imports.extend(data.hoisted().map(|scattered| scattered.import.clone()));
+        let imports = data.sorter.sort(&imports);
+        let rest = data.kept_rest();
+
+        // Statements are located by their index in the original imports.
+        // Without original imports, leading comments stay before the hoisted ones.
+        let header_len = data.imports.len();
+        let is_leading = |i: usize| i < header_len || (i == 0 && header_len == 0);
+
+        let mut text_imports = data
+            .directives
+            .iter()
+            .fold(String::new(), |acc, d| acc + &d.code() + "\n");
         let mut last_import_type = imports
             .first()
-            .map_or(ImportType::default(), |imp| imp.ty());
+            .map_or(ImportType::default(), |imp| data.sorter.ty(imp));
 
         for (i, import) in imports.iter().enumerate() {
-            // Add empty line between different import types
-            if last_import_type != import.ty() {
-                last_import_type = import.ty();
-                text_imports.push('\n');
+            // Add empty lines and a header between different import types
+            let import_type = data.sorter.ty(import);
+            if last_import_type != import_type {
+                last_import_type = import_type;
+                text_imports.push_str(&"\n".repeat(data.sorter.group_blank_lines()));
+                Self::push_header(&mut text_imports, data, import_type);
             }
 
             // Add comments if there are
-            if let Some(statements) = data.statements.get(&i) {
+            if let Some(statements) = data.statements.get(&i).filter(|_| is_leading(i)) {
                 let comments = statements
                     .iter()
                     .fold(String::new(), |acc, s| acc + &s.code() + "\n");
                 text_imports.push_str(&comments);
             }
 
+            // The header of the first group comes after the leading comments
+            if i == 0 {
+                Self::push_header(&mut text_imports, data, import_type);
+            }
+
             // Add import
             text_imports.push_str(&import.code());
             text_imports.push('\n');
         }
 
         // If there are comments after imports, add them
-        if let Some(statements) = data.statements.get(&imports.len()) {
+        let trailing = data
+            .statements
+            .get(&header_len)
+            .filter(|_| !is_leading(header_len) || imports.is_empty());
+        if let Some(statements) = trailing {
             // Add empty line if there are imports
-            if !data.imports.is_empty() {
+            if !imports.is_empty() {
                 text_imports.push('\n');
             }
             let comments = statements
@@ -73,17 +324,37 @@ impl File {
                 .fold(String::new(), |acc, s| acc + &s.code() + "\n");
             text_imports.push_str(&comments);
 
+            // Code, unlike comments, is separated from the rest
+            if statements.last().is_some_and(|s| s.is_code()) && !rest.is_empty() {
+                text_imports.push('\n');
+            }
+
         // Don't add empty lines if the file does not contain imports
         // This is a `else if` because we don't want empty lines after comments
-        } else if !imports.is_empty() && !data.rest.is_empty() {
+        } else if (!imports.is_empty() || !data.directives.is_empty()) && !rest.is_empty() {
             text_imports.push('\n');
         }
 
-        let content = text_imports + &data.rest;
+        let mut content = data.line_ending.apply(&text_imports);
 
-        fs::write(self.path.clone(), content)?;
+        // Keep the absence of trailing newline when there is nothing after the imports
+        if rest.is_empty() && !data.trailing_newline {
+            content.truncate(content.trim_end_matches(['\r', '\n']).len());
+        }
 
-        Ok(())
+        if data.bom {
+            content.insert(0, BOM);
+        }
+
+        content + &rest
+    }
+
+    /// Adds the header of a group, if enabled.
+    fn push_header(text: &mut String, data: &FileData, ty: ImportType) {
+        if let Some(header) = data.sorter.header(ty) {
+            text.push_str(&header.code());
+            text.push('\n');
+        }
     }
 
     /// Check whether imports are correctly sorted in this file.
@@ -91,38 +362,234 @@ impl File {
     /// Return [true] if they are, [false] otherwise.
     #[must_use]
     pub fn check(&self, data: &FileData) -> bool {
-        let mut imports = data.imports.clone();
-        imports.sort();
-
-        data.imports == imports
+        data.is_sorted()
     }
 
     /// Get the import data.
-    pub fn parse(&self) -> Result<FileData, Error> {
-        let mut program = fs::read_to_string(&self.path)?;
+    ///
+    /// Imports are classified using the settings of the project containing the file,
+    /// and sorted following the `config` options.
+    pub fn parse(&self, config: &Config) -> Result<FileData, Error> {
+        let sorter = Sorter::new(config, Project::for_file(&self.path));
+
+        Ok(Self::parse_with(
+            &self.read()?,
+            Format::of(&self.path, config),
+            sorter,
+        ))
+    }
+
+    /// Reads the file content.
+    ///
+    /// The content of staged files is read from the git index.
+    pub fn read(&self) -> Result<String, Error> {
+        if self.staged {
+            git::read_staged(&self.path)
+        } else {
+            Ok(fs::read_to_string(&self.path)?)
+        }
+    }
+
+    /// Parses the import data out of a file content, with the default [Sorter].
+    #[cfg(test)]
+    pub(crate) fn parse_source(source: &str) -> FileData {
+        Self::parse_with(source, Format::Script, Sorter::default())
+    }
+
+    /// Parses the import data out of a file content.
+    ///
+    /// The scripts of container files are parsed separately, see [Format].
+    pub(crate) fn parse_with(source: &str, format: Format, sorter: Sorter) -> FileData {
+        if !format.is_container() {
+            return Self::parse_script(source, 1, sorter);
+        }
+
         let mut data = FileData::new();
+        data.source = source.to_owned();
+        data.format = format;
+        data.blocks = format
+            .scripts(source)
+            .into_iter()
+            .map(|range| {
+                let script = &source[range.clone()];
+                let indent = format::indentation(script).to_owned();
+                let first_line = source[..range.start].matches('\n').count() + 1;
+                let mut data = Self::parse_script(
+                    &format::dedent(script, &indent),
+                    first_line,
+                    sorter.clone(),
+                );
+
+                // Columns are counted in the original, indented, lines
+                for diagnostic in &mut data.diagnostics {
+                    diagnostic.column += indent.chars().count();
+                }
+
+                Block {
+                    range,
+                    indent,
+                    data,
+                }
+            })
+            .collect();
+        data.sorter = sorter;
+
+        data
+    }
+
+    /// Parses the import data out of a script, which starts at `first_line` of its file.
+    ///
+    /// The byte order mark is stripped before parsing.
+    fn parse_script(source: &str, first_line: usize, sorter: Sorter) -> FileData {
+        let mut data = FileData::new();
+        data.source = source.to_owned();
+        data.sorter = sorter;
+
+        let mut program = match source.strip_prefix(BOM) {
+            Some(program) => {
+                data.bom = true;
+                program.to_owned()
+            }
+            None => source.to_owned(),
+        };
+        data.line_ending = LineEnding::detect(&program);
+        data.trailing_newline = program.is_empty() || program.ends_with('\n');
+
+        // Line number of the start of `program`
+        let mut line = first_line;
 
         loop {
-            if let Ok((input, import)) = import_statement(&program) {
+            // Directives found between imports are moved up too
+            if let Ok((input, directive)) = triple_slash_directive(&program) {
+                data.misplaced_directives |= !data.imports.is_empty();
+                data.directives.push(directive);
+                line += Self::count_lines(&program, input);
+                program = input.to_owned();
+            } else if let Ok((input, import)) = import_statement(&program) {
                 data.imports.push(import);
+                data.import_lines.push(line);
+                line += Self::count_lines(&program, input);
                 program = input.to_owned();
             } else if let Ok((input, comment)) = comment(&program) {
-                if let Some(tmp) = data.statements.get_mut(&data.imports.len()) {
-                    tmp.push(Box::new(comment));
-                } else {
-                    data.statements
-                        .insert(data.imports.len(), vec![Box::new(comment)]);
+                // Generated headers are regenerated when rendering
+                if data.sorter.is_header(&comment) && Self::starts_with_import(input) {
+                    line += Self::count_lines(&program, input);
+                    program = input.to_owned();
+                    continue;
                 }
+
+                let index = data.imports.len();
+                data.statements
+                    .entry(index)
+                    .or_default()
+                    .push(Box::new(comment));
+                data.statement_lines.entry(index).or_default().push(line);
+                line += Self::count_lines(&program, input);
+                program = input.to_owned();
+            } else if let Ok((input, alias)) = namespace_alias(&program) {
+                // Aliases depend on the imports before them, they are kept in place
+                let index = data.imports.len();
+                data.statements
+                    .entry(index)
+                    .or_default()
+                    .push(Box::new(alias));
+                data.statement_lines.entry(index).or_default().push(line);
+                line += Self::count_lines(&program, input);
                 program = input.to_owned();
             } else if let Ok((input, _)) = empty_line(&program) {
+                line += Self::count_lines(&program, input);
                 program = input.to_owned();
             } else {
+                let diagnostic = ParseDiagnostic::of(&program, line);
+                // The imports are cut short by an import which is not understood, the ones after
+                // it are not scattered
+                if !diagnostic
+                    .as_ref()
+                    .is_some_and(|diagnostic| diagnostic.import)
+                {
+                    data.scattered = Self::find_scattered(&program, line);
+                }
+                data.diagnostics.extend(diagnostic);
                 data.rest = program;
                 break;
             }
         }
 
-        Ok(data)
+        data
+    }
+
+    /// Finds the top-level imports of `rest`, which starts at `first_line` of its file.
+    ///
+    /// Top-level imports are the import statements starting a line, without indentation.
+    /// Lines in strings, template literals or comments are skipped, see [Lexer].
+    /// They can't be moved up when the rest starts with a directive, like `'use client';`.
+    fn find_scattered(rest: &str, first_line: usize) -> Vec<ScatteredImport> {
+        let mut scattered = Vec::new();
+        let mut offset = 0;
+        let mut line = first_line;
+        // Lines in strings, template literals and comments are not code
+        let mut lexer = Lexer::default();
+
+        while offset < rest.len() {
+            let program = &rest[offset..];
+            let line_end = program.find('\n').map_or(program.len(), |i| i + 1);
+
+            let parsed = match lexer.in_code() {
+                true => import_statement(program).ok(),
+                false => None,
+            };
+            let Some((input, import)) = parsed else {
+                lexer.read(&program[..line_end]);
+                offset += line_end;
+                line += 1;
+                continue;
+            };
+
+            // The import ends with its line, unless other code follows it
+            let consumed = program.len() - input.len();
+            let input_line_end = input.find('\n').map_or(input.len(), |i| i + 1);
+            let alone = input[..input_line_end].trim().is_empty();
+            let end = consumed + input_line_end;
+            lexer.read(&input[..input_line_end]);
+            let before = &rest[..offset];
+            // Directives, like `'use client';`, must stay first
+            let after_directive = rest.starts_with(['\'', '"']);
+
+            scattered.push(ScatteredImport {
+                import,
+                line,
+                range: offset..offset + end,
+                movable: alone
+                    && !after_directive
+                    && !MOCK_CALLS.iter().any(|call| before.contains(call)),
+            });
+
+            line += program[..end].matches('\n').count();
+            offset += end;
+        }
+
+        scattered
+    }
+
+    /// Tells whether the next statement of `program` is an import, skipping empty lines and
+    /// comments.
+    fn starts_with_import(mut program: &str) -> bool {
+        loop {
+            if let Ok((input, _)) = empty_line(program) {
+                program = input;
+            } else if let Ok((input, _)) = comment(program) {
+                program = input;
+            } else {
+                break;
+            }
+        }
+
+        import_statement(program).is_ok()
+    }
+
+    /// Counts the lines consumed from `program` by a parser which left `input`.
+    fn count_lines(program: &str, input: &str) -> usize {
+        program[..program.len() - input.len()].matches('\n').count()
     }
 
     /// Gets the file path
@@ -142,3 +609,547 @@ impl From<File> for PathBuf {
         Self::from(value.path())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(source: &str) -> String {
+        File::render(&File::parse_source(source))
+    }
+
+    #[test]
+    fn sort_lf() {
+        assert_eq!(
+            sort("import b from 'b';\nimport a from 'a';\n\nfoo();\n"),
+            "import a from 'a';\nimport b from 'b';\n\nfoo();\n"
+        );
+    }
+
+    #[test]
+    fn keep_crlf() {
+        assert_eq!(
+            sort("import b from 'b';\r\nimport a from 'a';\r\n\r\nfoo();\r\n"),
+            "import a from 'a';\r\nimport b from 'b';\r\n\r\nfoo();\r\n"
+        );
+    }
+
+    #[test]
+    fn keep_bom() {
+        let data = File::parse_source("\u{feff}import b from 'b';\nimport a from 'a';\n");
+
+        assert!(data.bom);
+        assert_eq!(data.imports.len(), 2);
+        assert_eq!(
+            File::render(&data),
+            "\u{feff}import a from 'a';\nimport b from 'b';\n"
+        );
+    }
+
+    #[test]
+    fn keep_missing_trailing_newline() {
+        assert_eq!(
+            sort("import b from 'b';\nimport a from 'a';"),
+            "import a from 'a';\nimport b from 'b';"
+        );
+        assert_eq!(
+            sort("import b from 'b';\nimport a from 'a';\n\nfoo();"),
+            "import a from 'a';\nimport b from 'b';\n\nfoo();"
+        );
+
+        let config = Config {
+            hoist: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            sort_with("import b from 'b';\nfoo();\nimport a from 'a';", &config),
+            "import a from 'a';\nimport b from 'b';\n\nfoo();"
+        );
+    }
+
+    fn sort_with(source: &str, config: &Config) -> String {
+        File::render(&File::parse_with(
+            source,
+            Format::Script,
+            Sorter::new(config, Default::default()),
+        ))
+    }
+
+    #[test]
+    fn group_blank_lines() {
+        let source = "import b from './b';\nimport a from 'a';\n";
+        let config = |group_blank_lines| Config {
+            group_blank_lines,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            sort_with(source, &config(0)),
+            "import a from 'a';\nimport b from './b';\n"
+        );
+        assert_eq!(
+            sort_with(source, &config(2)),
+            "import a from 'a';\n\n\nimport b from './b';\n"
+        );
+    }
+
+    #[test]
+    fn group_headers() {
+        let config = Config {
+            group_headers: true,
+            ..Default::default()
+        };
+        let source = "// License\nimport b from './b';\nimport a from 'a';\n\nfoo();\n";
+        let sorted = "// License\n// Modules\nimport a from 'a';\n\n// Local imports\nimport b from './b';\n\nfoo();\n";
+
+        assert_eq!(sort_with(source, &config), sorted);
+
+        // Headers are recognized, and regenerated
+        let data = File::parse_with(
+            sorted,
+            Format::Script,
+            Sorter::new(&config, Default::default()),
+        );
+        assert!(!data.is_dangerous());
+        assert_eq!(data.statements[&0].len(), 1);
+        assert!(File::verify(&data, sorted).is_ok());
+
+        let moved = "// Modules\nimport b from './b';\n// Local imports\nimport a from 'a';\n";
+        assert_eq!(
+            sort_with(moved, &config),
+            "// Modules\nimport a from 'a';\n\n// Local imports\nimport b from './b';\n"
+        );
+    }
+
+    #[test]
+    fn custom_group_headers() {
+        let config = Config {
+            group_headers: true,
+            group_header: vec!["module=External".to_owned(), "local=Internal".to_owned()],
+            ..Default::default()
+        };
+        let source = "import b from './b';\nimport a from 'a';\n";
+        let sorted = "// External\nimport a from 'a';\n\n// Internal\nimport b from './b';\n";
+
+        assert_eq!(sort_with(source, &config), sorted);
+        assert_eq!(sort_with(sorted, &config), sorted);
+    }
+
+    #[test]
+    fn headers_kept_when_disabled() {
+        let data = File::parse_source("import b from './b';\n// Modules\nimport a from 'a';\n");
+
+        assert!(data.is_dangerous());
+    }
+
+    #[test]
+    fn sort_vue_scripts() {
+        let source = "<template>\n  <div />\n</template>\n\n<script lang=\"ts\">\nimport b from 'b';\nimport a from 'a';\nexport default {};\n</script>\n\n<script setup lang=\"ts\">\n  import d from './d';\n  import c from 'c';\n</script>\n";
+        let data = File::parse_with(source, Format::Vue, Sorter::default());
+
+        assert!(!data.is_sorted());
+        assert_eq!(data.blocks.len(), 2);
+        assert_eq!(data.blocks[1].data.import_lines, vec![12, 13]);
+
+        let sorted = File::render(&data);
+        assert_eq!(
+            sorted,
+            "<template>\n  <div />\n</template>\n\n<script lang=\"ts\">\nimport a from 'a';\nimport b from 'b';\n\nexport default {};\n</script>\n\n<script setup lang=\"ts\">\n  import c from 'c';\n\n  import d from './d';\n</script>\n"
+        );
+        assert!(File::verify(&data, &sorted).is_ok());
+        assert!(File::parse_with(&sorted, Format::Vue, Sorter::default()).is_sorted());
+    }
+
+    #[test]
+    fn sort_svelte_scripts() {
+        let source = "<script context=\"module\">\n\timport b from 'b';\n\timport a from 'a';\n</script>\n\n<h1>Hello</h1>\n";
+        let data = File::parse_with(source, Format::Svelte, Sorter::default());
+
+        assert_eq!(data.blocks[0].data.import_lines, vec![2, 3]);
+        assert_eq!(
+            File::render(&data),
+            "<script context=\"module\">\n\timport a from 'a';\n\timport b from 'b';\n</script>\n\n<h1>Hello</h1>\n"
+        );
+    }
+
+    #[test]
+    fn sort_astro_frontmatter() {
+        let source = "---\nimport Layout from '../layouts/Layout.astro';\nimport { z } from 'zod';\n---\n<Layout />\n";
+        let data = File::parse_with(source, Format::Astro, Sorter::default());
+
+        assert_eq!(data.blocks[0].data.import_lines, vec![2, 3]);
+        assert_eq!(
+            File::render(&data),
+            "---\nimport { z } from 'zod';\n\nimport Layout from '../layouts/Layout.astro';\n---\n<Layout />\n"
+        );
+    }
+
+    #[test]
+    fn sort_mdx_imports() {
+        let source = "import { Button } from './Button';\nimport { Meta } from '@storybook/blocks';\n\n# Button\n\n- usage\n\n  ```tsx\n  import b from 'b';\n  import a from 'a';\n  ```\n";
+        let data = File::parse_with(source, Format::Mdx { code_blocks: true }, Sorter::default());
+        let sorted = File::render(&data);
+
+        assert_eq!(
+            sorted,
+            "import { Meta } from '@storybook/blocks';\n\nimport { Button } from './Button';\n\n# Button\n\n- usage\n\n  ```tsx\n  import a from 'a';\n  import b from 'b';\n  ```\n"
+        );
+        assert!(File::verify(&data, &sorted).is_ok());
+    }
+
+    #[test]
+    fn find_scattered_imports() {
+        let data = File::parse_source(
+            "import a from 'a';\n\nconst b = 1;\nimport c from 'c';\n  import d from 'd';\njest.mock('e');\nimport e from 'e';\nimport f from 'f'; f();\nimport('g');\n",
+        );
+
+        let scattered: Vec<_> = data
+            .scattered
+            .iter()
+            .map(|s| (s.import.module.as_str(), s.line, s.movable))
+            .collect();
+        assert_eq!(
+            scattered,
+            vec![("c", 4, true), ("e", 7, false), ("f", 8, false)]
+        );
+        assert!(data.has_scattered());
+    }
+
+    #[test]
+    fn no_scattered_imports_in_strings_or_comments() {
+        let config = Config {
+            hoist: true,
+            ..Default::default()
+        };
+        for source in [
+            "import a from 'a';\n\nconst code = `\nimport b from 'b';\n`;\n",
+            "import a from 'a';\n\nconst code = `${x}\nimport b from 'b';\n${`\nimport c from 'c';\n`}`;\n",
+            "import a from 'a';\n\n/*\nimport c from 'c';\n*/\nfoo();\n",
+            "import a from 'a';\n\nfoo(); /* a\n * b\nimport c from 'c';\n*/\n",
+            "import a from 'a';\n\nconst code = 'line \\\nimport b from \\'b\\';\\\n';\n",
+            "import a from 'a';\r\n\r\nconst code = \"line \\\r\nimport b from 'b';\";\r\n",
+        ] {
+            let data = File::parse_with(
+                source,
+                Format::Script,
+                Sorter::new(&config, Default::default()),
+            );
+
+            assert!(!data.has_scattered(), "{source}");
+            assert_eq!(File::render(&data), source);
+        }
+    }
+
+    #[test]
+    fn scattered_imports_after_strings_and_comments() {
+        let data = File::parse_source(
+            "import a from 'a';\n\nconst code = `\nimport b from 'b';\n`; /*\nimport c from 'c';\n*/\nimport d from 'd'; const e = `\nimport e from 'e';\n`;\nimport f from 'f';\n",
+        );
+        let scattered: Vec<_> = data
+            .scattered
+            .iter()
+            .map(|s| (s.import.module.as_str(), s.line))
+            .collect();
+
+        assert_eq!(scattered, vec![("d", 8), ("f", 11)]);
+    }
+
+    #[test]
+    fn dynamic_imports() {
+        let source = "import b from 'b';\nimport ('./lazy')\nimport a from 'a';\n";
+        let data = File::parse_source(source);
+
+        assert_eq!(data.imports.len(), 1);
+        assert_eq!(data.scattered.len(), 1);
+        assert_eq!(data.scattered[0].import.module, "a");
+
+        let source = "import b from 'b';\nimport a from 'a';\n\nconst { MODE } = import.meta.env;\nconst lazy = await import('./lazy');\nimport.meta.hot?.accept();\n";
+        let data = File::parse_source(source);
+
+        assert!(!data.has_scattered());
+        assert_eq!(
+            File::render(&data),
+            "import a from 'a';\nimport b from 'b';\n\nconst { MODE } = import.meta.env;\nconst lazy = await import('./lazy');\nimport.meta.hot?.accept();\n"
+        );
+
+        let source = "import.meta.hot?.accept();\nimport a from 'a';\n";
+        assert!(File::parse_source(source).imports.is_empty());
+        assert_eq!(sort(source), source);
+    }
+
+    #[test]
+    fn parse_diagnostics() {
+        let data = File::parse_source("import b from 'b';\nimport a from a;\nimport c from 'c';\n");
+        let diagnostics: Vec<_> = data.diagnostics().map(|d| (d.line, d.column)).collect();
+
+        assert_eq!(diagnostics, vec![(2, 15)]);
+        assert_eq!(data.imports.len(), 1);
+        assert!(!data.has_scattered());
+
+        let data = File::parse_source("import a from a;\n\nfoo();\nimport b from 'b';\n");
+        assert!(data.diagnostics().next().is_some());
+        assert!(!data.has_scattered());
+
+        let data = File::parse_source("import a from 'a';\n\nexport { b } from 'b';\n");
+        assert_eq!(data.diagnostics().next().map(|d| d.line), Some(3));
+
+        let data = File::parse_source("import a from 'a';\n\nfoo();\nimport b from b;\n");
+        assert_eq!(data.diagnostics().next(), None);
+    }
+
+    #[test]
+    fn vue_parse_diagnostics() {
+        let source = "<template>\n  <div />\n</template>\n\n<script>\n  import b from 'b';\n  import a from \"a';\n</script>\n";
+        let data = File::parse_with(source, Format::Vue, Default::default());
+        let diagnostics: Vec<_> = data.diagnostics().map(|d| (d.line, d.column)).collect();
+
+        assert_eq!(diagnostics, vec![(7, 21)]);
+    }
+
+    #[test]
+    fn flow_type_imports() {
+        let source = "// @flow\nimport typeof Foo from './foo';\nimport type { Bar } from 'bar';\nimport { baz } from './foo';\nimport type from 'type';\n";
+        let data = File::parse_source(source);
+
+        assert!(!data.is_dangerous());
+        assert_eq!(
+            File::render(&data),
+            "// @flow\nimport type { Bar } from 'bar';\nimport type from 'type';\n\nimport typeof Foo from './foo';\nimport { baz } from './foo';\n"
+        );
+    }
+
+    #[test]
+    fn hoist_scattered_imports() {
+        let config = Config {
+            hoist: true,
+            ..Default::default()
+        };
+        let source = "// License\nimport b from 'b';\n\nconst x = 1;\n\nimport a from 'a';\n\nfoo();\njest.mock('c');\nimport c from 'c';\n";
+        let data = File::parse_with(
+            source,
+            Format::Script,
+            Sorter::new(&config, Default::default()),
+        );
+        let sorted = File::render(&data);
+
+        assert_eq!(
+            sorted,
+            "// License\nimport a from 'a';\nimport b from 'b';\n\nconst x = 1;\n\nfoo();\njest.mock('c');\nimport c from 'c';\n"
+        );
+        assert!(File::verify(&data, &sorted).is_ok());
+        // Not hoisted by default
+        assert_eq!(sort(source), source);
+    }
+
+    #[test]
+    fn hoist_without_imports() {
+        let config = Config {
+            hoist: true,
+            ..Default::default()
+        };
+        let source = "// License\nconst x = 1;\nimport a from 'a';\n";
+        let data = File::parse_with(
+            source,
+            Format::Script,
+            Sorter::new(&config, Default::default()),
+        );
+
+        assert_eq!(
+            File::render(&data),
+            "// License\nimport a from 'a';\n\nconst x = 1;\n"
+        );
+
+        // Directives must stay first
+        let data = File::parse_source("'use client';\nimport a from 'a';\n");
+        assert!(!data.scattered[0].movable);
+    }
+
+    #[test]
+    fn sort_import_require() {
+        assert_eq!(
+            sort("import path = require('path');\nimport fs = require('fs');\nimport a from './a';\n"),
+            "import fs = require('fs');\nimport path = require('path');\n\nimport a from './a';\n"
+        );
+    }
+
+    #[test]
+    fn keep_namespace_aliases() {
+        let source =
+            "import b from 'b';\nimport a from 'a';\nexport import Foo = a.Foo;\nconst x = 1;\n";
+        let data = File::parse_source(source);
+
+        assert_eq!(data.imports.len(), 2);
+        assert!(!data.is_dangerous());
+        assert_eq!(
+            File::render(&data),
+            "import a from 'a';\nimport b from 'b';\n\nexport import Foo = a.Foo;\n\nconst x = 1;\n"
+        );
+
+        let data =
+            File::parse_source("import b from 'b';\nimport Foo = b.Foo;\nimport a from 'a';\n");
+        assert!(data.is_dangerous());
+    }
+
+    #[test]
+    fn directives_first() {
+        let source = "// License\n/// <reference types=\"vite/client\" />\nimport b from 'b';\n/// <reference path=\"./a.d.ts\" />\nimport a from 'a';\n";
+        let data = File::parse_source(source);
+
+        assert_eq!(data.directives.len(), 2);
+        assert!(!data.is_dangerous());
+        assert_eq!(
+            File::render(&data),
+            "/// <reference types=\"vite/client\" />\n/// <reference path=\"./a.d.ts\" />\n// License\nimport a from 'a';\nimport b from 'b';\n"
+        );
+
+        let source = "import b from 'b';\n/// <reference types=\"node\" />\nimport a from 'a';\n";
+        let data = File::parse_source(source);
+        assert!(!data.is_dangerous());
+        assert!(!data.is_sorted());
+        assert_eq!(
+            File::render(&data),
+            "/// <reference types=\"node\" />\nimport a from 'a';\nimport b from 'b';\n"
+        );
+        assert!(File::verify(&data, &File::render(&data)).is_ok());
+
+        let source = "/// <reference types=\"vite/client\" />\n// License\nimport b from 'b';\nimport a from 'a';\n";
+        let data = File::parse_source(source);
+        assert!(!data.is_dangerous());
+        assert_eq!(
+            File::render(&data),
+            "/// <reference types=\"vite/client\" />\n// License\nimport a from 'a';\nimport b from 'b';\n"
+        );
+
+        let source = "/// <reference types=\"vite/client\" />\n\nconst x = 1;\n";
+        assert_eq!(sort(source), source);
+    }
+
+    #[test]
+    fn line_numbers() {
+        let data = File::parse_source(
+            "// header\nimport b from 'b';\n\n/*\n * a\n */\nimport a from 'a';\n",
+        );
+
+        assert_eq!(data.import_lines, vec![2, 7]);
+        assert_eq!(data.statement_lines[&0], vec![1]);
+        assert_eq!(data.statement_lines[&1], vec![4]);
+    }
+
+    mod verify {
+        use super::*;
+
+        #[test]
+        fn sorted_content_is_valid() {
+            let source = "// header\nimport b from 'b'; // b\nimport a from 'a';\n\nfoo();\n";
+            let data = File::parse_source(source);
+
+            assert!(File::verify(&data, &File::render(&data)).is_ok());
+        }
+
+        #[test]
+        fn missing_import() {
+            let data = File::parse_source("import b from 'b';\nimport a from 'a';\n");
+
+            assert!(File::verify(&data, "import a from 'a';\n").is_err());
+        }
+
+        #[test]
+        fn changed_identifiers() {
+            let data = File::parse_source("import { b } from 'b';\n");
+
+            assert!(File::verify(&data, "import b from 'b';\n").is_err());
+        }
+
+        #[test]
+        fn missing_comment() {
+            let data = File::parse_source("// foo\nimport a from 'a';\n");
+
+            assert!(File::verify(&data, "import a from 'a';\n").is_err());
+        }
+
+        #[test]
+        fn changed_around_scripts() {
+            let source = "<script>\nimport a from 'a';\n</script>\n<template />\n";
+            let data = File::parse_with(source, Format::Vue, Sorter::default());
+
+            assert!(File::verify(&data, source).is_ok());
+            assert!(File::verify(&data, &source.replace("template", "div")).is_err());
+        }
+
+        #[test]
+        fn changed_rest() {
+            let data = File::parse_source("import a from 'a';\n\nfoo();\n");
+
+            assert!(File::verify(&data, "import a from 'a';\n\nfoo( );\n").is_err());
+        }
+
+        #[test]
+        fn not_idempotent() {
+            let data = File::parse_source("import b from 'b';\nimport a from 'a';\n");
+
+            assert!(File::verify(&data, "import b from 'b';\nimport a from 'a';\n").is_err());
+        }
+    }
+
+    mod write {
+        use super::*;
+
+        #[test]
+        fn replace_content() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("index.js");
+            fs::write(&path, "old").unwrap();
+
+            File::new(&path).write("new", None).unwrap();
+
+            assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+            assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        }
+
+        #[test]
+        fn backup() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("index.js");
+            fs::write(&path, "old").unwrap();
+
+            File::new(&path).write("new", Some(".orig")).unwrap();
+
+            assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+            assert_eq!(
+                fs::read_to_string(dir.path().join("index.js.orig")).unwrap(),
+                "old"
+            );
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn keep_permissions() {
+            use std::os::unix::fs::PermissionsExt;
+
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("index.js");
+            fs::write(&path, "old").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+            File::new(&path).write("new", None).unwrap();
+
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn keep_symlink() {
+            let dir = tempfile::tempdir().unwrap();
+            let target = dir.path().join("target.js");
+            let link = dir.path().join("link.js");
+            fs::write(&target, "old").unwrap();
+            std::os::unix::fs::symlink(&target, &link).unwrap();
+
+            File::new(&link).write("new", None).unwrap();
+
+            assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+            assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        }
+    }
+}
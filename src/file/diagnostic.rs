@@ -0,0 +1,188 @@
+//! Explains why the imports of a script stopped early, see [ParseDiagnostic].
+
+use std::fmt::Display;
+
+use crate::parser::import_statement;
+
+/// Maximum number of characters of a [ParseDiagnostic::snippet].
+const SNIPPET_LENGTH: usize = 60;
+
+/// Explains why the imports of a script stopped before a statement looking like an import.
+///
+/// The statement and everything after it are left as is, and are not sorted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    /// Line number, in the original file.
+    pub line: usize,
+    /// Column number, in characters, starting at 1.
+    pub column: usize,
+    /// Why the statement was not understood.
+    pub message: &'static str,
+    /// The line which was not understood, starting at the statement.
+    pub snippet: String,
+    /// Whether the statement is an import, rather than a re-export.
+    pub import: bool,
+}
+
+impl ParseDiagnostic {
+    /// Diagnoses `program`, the rest of a script which starts at `line`, if it starts like an
+    /// import or a re-export.
+    pub(crate) fn of(program: &str, line: usize) -> Option<Self> {
+        let statement = program.trim_start_matches([' ', '\t']);
+
+        let import = looks_like_import(statement);
+        let (message, unparsed) = if import {
+            match import_statement(statement) {
+                Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                    ("unexpected syntax in import", e.input)
+                }
+                _ if statement.len() < program.len() => {
+                    ("import not at the start of a line", statement)
+                }
+                // Understood
+                _ => return None,
+            }
+        } else if is_reexport(statement) {
+            ("re-exports are not sorted", statement)
+        } else {
+            return None;
+        };
+
+        let parsed = &program[..program.len() - unparsed.len()];
+        let line_start = parsed.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = unparsed.find(['\r', '\n']).unwrap_or(unparsed.len());
+
+        let line_text = program[line_start..parsed.len() + line_end].trim();
+        let mut snippet: String = line_text.chars().take(SNIPPET_LENGTH).collect();
+        if snippet.len() < line_text.len() {
+            snippet.push('…');
+        }
+
+        Some(Self {
+            line: line + parsed.matches('\n').count(),
+            column: parsed[line_start..].chars().count() + 1,
+            message,
+            snippet,
+            import,
+        })
+    }
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: `{}`", self.message, self.snippet)
+    }
+}
+
+/// Tells whether `statement` starts like an import declaration.
+///
+/// Dynamic `import()` and `import.meta` are expressions, not declarations.
+fn looks_like_import(statement: &str) -> bool {
+    let Some(rest) = statement.strip_prefix("import") else {
+        return false;
+    };
+
+    rest.starts_with(|c: char| c.is_whitespace() || "{*'\"".contains(c))
+        && !rest.trim_start().starts_with(['(', '.'])
+}
+
+/// Tells whether `statement` is a re-export, like `export { a } from 'a';` or
+/// `export * as b from 'b';`.
+fn is_reexport(statement: &str) -> bool {
+    let Some(rest) = statement.strip_prefix("export") else {
+        return false;
+    };
+    let rest = rest.trim_start();
+    let rest = match rest.strip_prefix("type") {
+        Some(bindings) if bindings.starts_with(char::is_whitespace) => bindings.trim_start(),
+        _ => rest,
+    };
+
+    let rest = if let Some(bindings) = rest.strip_prefix('{') {
+        match bindings.find('}') {
+            Some(end) => &bindings[end + 1..],
+            None => return false,
+        }
+    } else if let Some(bindings) = rest.strip_prefix('*') {
+        match bindings.trim_start().strip_prefix("as") {
+            Some(name) if name.starts_with(char::is_whitespace) => name
+                .trim_start()
+                .trim_start_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '$'),
+            _ => bindings,
+        }
+    } else {
+        return false;
+    };
+
+    rest.trim_start()
+        .strip_prefix("from")
+        .is_some_and(|module| module.trim_start().starts_with(['\'', '"']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unexpected_syntax() {
+        let diagnostic = ParseDiagnostic::of("import x from 'x;\nfoo();\n", 3).unwrap();
+
+        assert_eq!(diagnostic.line, 3);
+        assert_eq!(diagnostic.column, 15);
+        assert_eq!(diagnostic.message, "unexpected syntax in import");
+        assert_eq!(diagnostic.snippet, "import x from 'x;");
+
+        let diagnostic = ParseDiagnostic::of("import {\n  a,\n} from `a`;\n", 1).unwrap();
+
+        assert_eq!((diagnostic.line, diagnostic.column), (3, 8));
+        assert_eq!(diagnostic.snippet, "} from `a`;");
+    }
+
+    #[test]
+    fn import_not_starting_line() {
+        let diagnostic = ParseDiagnostic::of(" import b from 'b';\n", 2).unwrap();
+
+        assert_eq!((diagnostic.line, diagnostic.column), (2, 2));
+        assert_eq!(diagnostic.message, "import not at the start of a line");
+        assert_eq!(diagnostic.snippet, "import b from 'b';");
+    }
+
+    #[test]
+    fn reexports() {
+        for reexport in [
+            "export { a } from 'a';",
+            "export {\n  a,\n} from \"a\"",
+            "export * from 'a';",
+            "export * as a from 'a';",
+            "export type { A } from './a';",
+        ] {
+            assert!(ParseDiagnostic::of(reexport, 1).is_some(), "{reexport}");
+        }
+    }
+
+    #[test]
+    fn other_code() {
+        for code in [
+            "foo();",
+            "import('./lazy');",
+            "import ('./lazy');",
+            "import.meta.env;",
+            "important();",
+            "export const from = 'a';",
+            "export { a };\nconst b = from('b');",
+            "export default from;",
+            "import { fromage } from 'cheese';",
+        ] {
+            assert_eq!(ParseDiagnostic::of(code, 1), None, "{code}");
+        }
+    }
+
+    #[test]
+    fn long_snippet() {
+        let code = format!("import x from '{}", "a".repeat(100));
+        let diagnostic = ParseDiagnostic::of(&code, 1).unwrap();
+
+        assert_eq!(diagnostic.snippet.chars().count(), SNIPPET_LENGTH + 1);
+        assert!(diagnostic.snippet.ends_with('…'));
+    }
+}
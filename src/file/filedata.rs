@@ -1,43 +1,190 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, ops::Range};
 
-use crate::statements::{ImportStatement, Statement};
+use crate::{
+    sorter::Sorter,
+    statements::{ImportStatement, Statement, TripleSlashDirective},
+};
+
+use super::{Format, LineEnding, ParseDiagnostic};
 
 /// This is the data resulting from a parsed [File](super::File).
 pub struct FileData {
-    pub(super) imports: Vec<ImportStatement>,
-    pub(super) statements: HashMap<usize, Vec<Box<dyn Statement>>>,
-    pub(super) rest: String,
+    /// Triple-slash directives of the import header, always written first.
+    pub(crate) directives: Vec<TripleSlashDirective>,
+    /// Whether directives were found after an import, and are moved up.
+    pub(crate) misplaced_directives: bool,
+    pub(crate) imports: Vec<ImportStatement>,
+    /// Line number of each import, in the original file.
+    pub(crate) import_lines: Vec<usize>,
+    pub(crate) statements: HashMap<usize, Vec<Box<dyn Statement>>>,
+    /// Line number of each statement, in the original file.
+    pub(crate) statement_lines: HashMap<usize, Vec<usize>>,
+    pub(crate) rest: String,
+    /// Imports found in the rest of the file, after other code.
+    pub(crate) scattered: Vec<ScatteredImport>,
+    /// Why the imports stopped, when the rest of the file starts like an import.
+    pub(crate) diagnostics: Vec<ParseDiagnostic>,
+    /// The original file content.
+    pub(crate) source: String,
+    /// Whether the file starts with a UTF-8 byte order mark.
+    pub(super) bom: bool,
+    /// The dominant line ending of the file.
+    pub(super) line_ending: LineEnding,
+    /// Whether the file ends with a line ending.
+    pub(super) trailing_newline: bool,
+    /// How the imports of the file are sorted.
+    pub(crate) sorter: Sorter,
+    /// The format of the file.
+    pub(crate) format: Format,
+    /// The scripts embedded in a container file, see [Format::is_container].
+    pub(crate) blocks: Vec<Block>,
+}
+
+/// A top-level import found after other code, in [FileData::rest].
+pub(crate) struct ScatteredImport {
+    pub(crate) import: ImportStatement,
+    /// Line number of the import, in the original file.
+    pub(crate) line: usize,
+    /// The byte range of the import lines in [FileData::rest].
+    pub(crate) range: Range<usize>,
+    /// Whether the import can safely be moved up into the sorted imports.
+    ///
+    /// Imports following a directive or a `jest.mock()` like call, or sharing their line with
+    /// other code, are never moved.
+    pub(crate) movable: bool,
+}
+
+/// A script embedded in a container file, like the `<script>` of a Vue component.
+pub(crate) struct Block {
+    /// The byte range of the script in the file.
+    pub(crate) range: Range<usize>,
+    /// The indentation of the script, removed before parsing it.
+    pub(crate) indent: String,
+    /// The parsed script.
+    pub(crate) data: FileData,
 }
 
 impl FileData {
     /// Creates a new [FileData] with default values.
     pub(super) fn new() -> Self {
         Self {
+            directives: Default::default(),
+            misplaced_directives: false,
             imports: Default::default(),
+            import_lines: Default::default(),
             statements: Default::default(),
+            statement_lines: Default::default(),
             rest: Default::default(),
+            scattered: Default::default(),
+            diagnostics: Default::default(),
+            source: Default::default(),
+            bom: false,
+            line_ending: Default::default(),
+            trailing_newline: true,
+            sorter: Default::default(),
+            format: Default::default(),
+            blocks: Default::default(),
         }
     }
+
+    /// Returns the parsed scripts of the file: the file itself, then its embedded blocks.
+    pub(crate) fn scripts(&self) -> impl Iterator<Item = &FileData> {
+        std::iter::once(self).chain(self.blocks.iter().map(|block| &block.data))
+    }
+
+    /// Tells whether the imports of every script are sorted, and written as they should be.
+    ///
+    /// Directives found after an import are not sorted either.
+    pub fn is_sorted(&self) -> bool {
+        self.scripts()
+            .all(|script| !script.misplaced_directives && script.sorter.is_sorted(&script.imports))
+    }
+
+    /// Tells whether imports are found after other code, in any script.
+    pub fn has_scattered(&self) -> bool {
+        self.scripts().any(|script| !script.scattered.is_empty())
+    }
+
+    /// Returns the scattered imports moved up into the sorted imports, see
+    /// [Config::hoist](crate::Config::hoist).
+    pub(crate) fn hoisted(&self) -> impl Iterator<Item = &ScatteredImport> {
+        self.scattered
+            .iter()
+            .filter(|scattered| scattered.movable && self.sorter.hoist())
+    }
+
+    /// Returns the rest of the script, without the [hoisted](FileData::hoisted) imports.
+    ///
+    /// A blank line following a hoisted import is removed too when the import was preceded by
+    /// a blank line, so that removing it does not leave two blank lines. The script still ends
+    /// without a newline if it did, even when its last import was hoisted.
+    pub(crate) fn kept_rest(&self) -> Cow<'_, str> {
+        let mut hoisted = self.hoisted().peekable();
+        if hoisted.peek().is_none() {
+            return Cow::Borrowed(&self.rest);
+        }
+
+        let mut rest = String::new();
+        let mut end = 0;
+
+        for scattered in hoisted {
+            let kept = &self.rest[end..scattered.range.start];
+            rest.push_str(kept);
+            end = scattered.range.end;
+
+            let after = &self.rest[end..];
+            let blank_after = after.find('\n').filter(|i| after[..*i].trim().is_empty());
+            if let (true, Some(i)) = (
+                rest.ends_with("\n\n") || rest.ends_with("\n\r\n"),
+                blank_after,
+            ) {
+                end += i + 1;
+            }
+        }
+
+        rest.push_str(&self.rest[end..]);
+        if !self.trailing_newline {
+            rest.truncate(rest.trim_end_matches(['\r', '\n']).len());
+        }
+        Cow::Owned(rest)
+    }
+
     /// Tells whether this file is dangerous to format.
     ///
     /// Formatting is dangerous when there are other statements in-between imports,
     /// like comments.
     pub fn is_dangerous(&self) -> bool {
-        let imports_len = self.imports.len();
-        self.statements.keys().any(|k| k > &0 && k < &imports_len)
+        self.scripts().any(|script| {
+            script
+                .statements
+                .keys()
+                .any(|k| script.is_dangerous_index(*k))
+        })
+    }
+
+    /// Returns the parse diagnostics of every script.
+    pub fn diagnostics(&self) -> impl Iterator<Item = &ParseDiagnostic> {
+        self.scripts().flat_map(|script| &script.diagnostics)
+    }
+
+    /// Tells whether statements located before the import at `index` are dangerous.
+    pub(crate) fn is_dangerous_index(&self, index: usize) -> bool {
+        index > 0 && index < self.imports.len()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::statements::{Comment, CommentType};
+    use crate::statements::{Comment, CommentType, ImportKind};
 
     fn import(module: &str) -> ImportStatement {
         ImportStatement {
             identifiers: None,
             module: module.to_owned(),
             comment: None,
+            kind: ImportKind::Esm,
+            type_only: None,
         }
     }
 
@@ -0,0 +1,468 @@
+//! Finds the scripts of container files, like Vue, Svelte & Astro components or MDX documents.
+
+use std::{ops::Range, path::Path};
+
+use crate::{
+    parser::{comment, empty_line, import_statement},
+    Config,
+};
+
+/// The format of a file, telling where its imports are.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// A JavaScript / TypeScript file, which imports are at the top of the file.
+    #[default]
+    Script,
+    /// A Vue single-file component, which imports are in its `<script>` and `<script setup>`
+    /// blocks.
+    Vue,
+    /// A Svelte component, which imports are in its `<script>` and
+    /// `<script context="module">` blocks.
+    Svelte,
+    /// An Astro component, which imports are in its `---` frontmatter.
+    Astro,
+    /// A MDX document, which imports are in its leading ESM block.
+    Mdx {
+        /// Whether imports in the fenced code blocks are sorted too.
+        code_blocks: bool,
+    },
+    /// A Markdown document, which imports are in its fenced code blocks, if sorted.
+    Markdown {
+        /// Whether imports in the fenced code blocks are sorted.
+        code_blocks: bool,
+    },
+}
+
+impl Format {
+    /// Returns the format of a file, by its extension.
+    ///
+    /// Fenced code blocks of Markdown documents are sorted with [Config::code_blocks].
+    pub fn of(path: &Path, config: &Config) -> Self {
+        let code_blocks = config.code_blocks;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("vue") => Self::Vue,
+            Some("svelte") => Self::Svelte,
+            Some("astro") => Self::Astro,
+            Some("mdx") => Self::Mdx { code_blocks },
+            Some("md" | "markdown") => Self::Markdown { code_blocks },
+            _ => Self::Script,
+        }
+    }
+
+    /// Tells whether the scripts of this format are embedded in another language.
+    pub fn is_container(&self) -> bool {
+        *self != Self::Script
+    }
+
+    /// Returns the byte ranges of the embedded scripts of `source`.
+    ///
+    /// A range starts at the first non-blank line of the script, and ends at the start of the
+    /// line closing it, so the rest of the line of the tags is kept as is.
+    pub fn scripts(&self, source: &str) -> Vec<Range<usize>> {
+        match self {
+            Self::Script => std::iter::once(0..source.len()).collect(),
+            Self::Vue | Self::Svelte => script_tags(source),
+            Self::Astro => frontmatter(source).into_iter().collect(),
+            Self::Mdx { code_blocks } => {
+                let mut scripts: Vec<_> = esm_imports(source).into_iter().collect();
+                if *code_blocks {
+                    let start = scripts.last().map_or(0, |esm| esm.end);
+                    scripts.extend(code_blocks_of(source, start));
+                }
+                scripts
+            }
+            Self::Markdown { code_blocks: true } => code_blocks_of(source, 0),
+            Self::Markdown { code_blocks: false } => Vec::new(),
+        }
+    }
+}
+
+/// Languages of the fenced code blocks of Markdown documents which are sorted.
+const CODE_BLOCK_LANGS: [&str; 8] = [
+    "js",
+    "jsx",
+    "ts",
+    "tsx",
+    "mjs",
+    "cjs",
+    "javascript",
+    "typescript",
+];
+
+/// Returns the lines of `source` from `start`, with their offset.
+fn lines_from(source: &str, start: usize) -> impl Iterator<Item = (usize, &str)> + Clone {
+    source[start..]
+        .split_inclusive('\n')
+        .scan(start, |offset, line| {
+            let line_start = *offset;
+            *offset += line.len();
+            Some((line_start, line))
+        })
+}
+
+/// Returns the range of the leading ESM import block of a MDX document, after its optional
+/// frontmatter.
+fn esm_imports(source: &str) -> Option<Range<usize>> {
+    let start = match frontmatter(source) {
+        Some(frontmatter) => lines_from(source, frontmatter.end).nth(1)?.0,
+        None => 0,
+    };
+    let start = lines_from(source, start)
+        .find(|(_, line)| !line.trim().is_empty())?
+        .0;
+
+    let mut program = &source[start..];
+    let mut end = start;
+
+    loop {
+        if let Ok((input, _)) = import_statement(program) {
+            program = input;
+            end = source.len() - input.len();
+        } else if let Ok((input, _)) = empty_line(program) {
+            program = input;
+        } else if let Ok((input, _)) = comment(program) {
+            program = input;
+        } else {
+            break;
+        }
+    }
+
+    (end > start).then_some(start..end)
+}
+
+/// Returns the content ranges of the fenced JavaScript / TypeScript code blocks of a Markdown
+/// document, from `start`.
+fn code_blocks_of(source: &str, start: usize) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut lines = lines_from(source, start);
+
+    while let Some((offset, line)) = lines.next() {
+        let Some((fence, info)) = fence(line) else {
+            continue;
+        };
+        let content_start = offset + line.len();
+        let Some((content_end, _)) = lines.find(|(_, line)| {
+            fence_of(line).is_some_and(|closing| {
+                closing.starts_with(fence) && line.trim_start()[closing.len()..].trim().is_empty()
+            })
+        }) else {
+            break;
+        };
+
+        let lang = info.split_whitespace().next().unwrap_or_default();
+        if CODE_BLOCK_LANGS.contains(&lang) {
+            let range = trim_lines(source, content_start..content_end);
+            if !range.is_empty() {
+                blocks.push(range);
+            }
+        }
+    }
+
+    blocks
+}
+
+/// Returns the fence opening a code block, like ` ``` `, and its info string.
+fn fence(line: &str) -> Option<(&str, &str)> {
+    let fence = fence_of(line)?;
+    let info = line.trim_start()[fence.len()..].trim();
+
+    (!fence.starts_with('`') || !info.contains('`')).then_some((fence, info))
+}
+
+/// Returns the fence characters starting a line, at least 3 backticks or tildes.
+fn fence_of(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let fence = &line[..line.len() - line.trim_start_matches(c).len()];
+
+    (fence.len() >= 3).then_some(fence)
+}
+
+/// Returns the content range of the `---` frontmatter starting an Astro component.
+fn frontmatter(source: &str) -> Option<Range<usize>> {
+    let mut lines = lines_from(source, 0);
+    let is_fence = |line: &str| line.trim_start_matches('\u{feff}').trim_end() == "---";
+
+    let (_, open) = lines.find(|(_, line)| !line.trim().is_empty())?;
+    if !is_fence(open) {
+        return None;
+    }
+    let content_start = lines.clone().next()?.0;
+    let (content_end, _) = lines.find(|(_, line)| is_fence(line))?;
+
+    let range = trim_lines(source, content_start..content_end);
+    (!range.is_empty()).then_some(range)
+}
+
+/// Languages of `<script lang="...">` tags which are sorted.
+const SCRIPT_LANGS: [&str; 4] = ["js", "jsx", "ts", "tsx"];
+
+/// Returns the content ranges of the top-level `<script>` tags of an HTML-like component.
+///
+/// Scripts of another language than [SCRIPT_LANGS], and HTML comments, are skipped.
+fn script_tags(source: &str) -> Vec<Range<usize>> {
+    let mut scripts = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = source[offset..].find('<').map(|i| offset + i) {
+        let rest = &source[start..];
+
+        if rest.starts_with("<!--") {
+            offset = rest.find("-->").map_or(source.len(), |i| start + i + 3);
+            continue;
+        }
+
+        let Some(attributes) = rest.strip_prefix("<script") else {
+            offset = start + 1;
+            continue;
+        };
+        if !attributes.starts_with(|c: char| c.is_whitespace() || c == '>') {
+            offset = start + 1;
+            continue;
+        }
+
+        let Some(tag_end) = tag_end(rest) else {
+            break;
+        };
+        let tag = &rest[..tag_end];
+        let content_start = start + tag_end + 1;
+        let Some(content_end) = source[content_start..]
+            .find("</script")
+            .map(|i| content_start + i)
+        else {
+            break;
+        };
+        offset = content_end;
+
+        let lang = attribute(tag, "lang");
+        if tag.ends_with('/') || lang.is_some_and(|lang| !SCRIPT_LANGS.contains(&lang)) {
+            continue;
+        }
+
+        let range = trim_lines(source, content_start..content_end);
+        if !range.is_empty() {
+            scripts.push(range);
+        }
+    }
+
+    scripts
+}
+
+/// Returns the index of the `>` closing the tag starting `tag`, skipping quoted values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+
+    tag.char_indices().find_map(|(i, c)| {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            _ => (),
+        }
+        None
+    })
+}
+
+/// Returns the value of an attribute of a tag, like `ts` for `lang` in `<script lang="ts"`.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+
+    while let Some(i) = rest.find(name) {
+        let before = rest[..i].chars().next_back();
+        let after = rest[i + name.len()..].trim_start();
+        rest = &rest[i + name.len()..];
+
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+
+        return match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+            _ => value.split(|c: char| c.is_whitespace() || c == '>').next(),
+        };
+    }
+
+    None
+}
+
+/// Shrinks `range` to its lines: leading blank lines and the indentation of the closing line
+/// are left out.
+fn trim_lines(source: &str, range: Range<usize>) -> Range<usize> {
+    let content = &source[range.clone()];
+    let mut start = 0;
+
+    while let Some(end) = content[start..].find('\n') {
+        if !content[start..start + end].trim().is_empty() {
+            break;
+        }
+        start += end + 1;
+    }
+
+    let last_line = content.rfind('\n').map_or(0, |i| i + 1);
+    let end = if content[last_line..].trim().is_empty() {
+        last_line
+    } else {
+        content.len()
+    };
+
+    range.start + start.min(end)..range.start + end
+}
+
+/// Returns the indentation shared by every non-blank line of `script`.
+pub fn indentation(script: &str) -> &str {
+    let mut lines = script.lines().filter(|line| !line.trim().is_empty());
+    let Some(first) = lines.next() else {
+        return "";
+    };
+    let indent = &first[..first.len() - first.trim_start().len()];
+
+    if lines.all(|line| line.starts_with(indent)) {
+        indent
+    } else {
+        ""
+    }
+}
+
+/// Removes `indent` from every non-blank line of `script`.
+pub fn dedent(script: &str, indent: &str) -> String {
+    script
+        .split_inclusive('\n')
+        .map(|line| match line.strip_prefix(indent) {
+            Some(stripped) if !line.trim().is_empty() => stripped,
+            _ => line,
+        })
+        .collect()
+}
+
+/// Adds `indent` to every non-blank line of `script`.
+pub fn indent(script: &str, indent: &str) -> String {
+    script
+        .split_inclusive('\n')
+        .map(|line| {
+            if line.trim().is_empty() {
+                line.to_owned()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scripts(format: Format, source: &str) -> Vec<&str> {
+        format
+            .scripts(source)
+            .into_iter()
+            .map(|range| &source[range])
+            .collect()
+    }
+
+    #[test]
+    fn format_of() {
+        assert_eq!(
+            Format::of(Path::new("src/App.vue"), &Config::default()),
+            Format::Vue
+        );
+        assert_eq!(
+            Format::of(Path::new("src/App.svelte"), &Config::default()),
+            Format::Svelte
+        );
+        assert_eq!(
+            Format::of(Path::new("src/index.astro"), &Config::default()),
+            Format::Astro
+        );
+        assert_eq!(
+            Format::of(Path::new("src/main.ts"), &Config::default()),
+            Format::Script
+        );
+    }
+
+    #[test]
+    fn vue_scripts() {
+        let source = "<template>\n  <div />\n</template>\n\n<script lang=\"ts\">\nimport b from 'b';\n</script>\n\n<script setup lang='ts'>\n\nimport a from 'a';\n</script>\n\n<style>\n</style>\n";
+
+        assert_eq!(
+            scripts(Format::Vue, source),
+            vec!["import b from 'b';\n", "import a from 'a';\n"]
+        );
+    }
+
+    #[test]
+    fn svelte_scripts() {
+        let source = "<script context=\"module\">\n  import b from 'b';\n</script>\n\n<script lang=\"ts\">\n  import a from 'a';\n</script>\n\n<h1>Hello</h1>\n";
+
+        assert_eq!(
+            scripts(Format::Svelte, source),
+            vec!["  import b from 'b';\n", "  import a from 'a';\n"]
+        );
+    }
+
+    #[test]
+    fn astro_frontmatter() {
+        let source =
+            "---\nimport b from 'b';\nconst title = 'Hello';\n---\n<h1>{title}</h1>\n---\n";
+
+        assert_eq!(
+            scripts(Format::Astro, source),
+            vec!["import b from 'b';\nconst title = 'Hello';\n"]
+        );
+        assert!(scripts(Format::Astro, "<h1>---</h1>\n---\n").is_empty());
+        assert!(scripts(Format::Astro, "---\nimport a from 'a';\n").is_empty());
+    }
+
+    #[test]
+    fn mdx_imports() {
+        let source = "---\ntitle: Button\n---\n\nimport { Button } from './Button';\nimport {\n  Meta,\n} from '@storybook/blocks';\n\n# Button\n\nimport a from 'a';\n";
+        let format = Format::Mdx { code_blocks: false };
+
+        assert_eq!(
+            scripts(format, source),
+            vec!["import { Button } from './Button';\nimport {\n  Meta,\n} from '@storybook/blocks';"]
+        );
+        assert!(scripts(format, "# Title\n\nimport a from 'a';\n").is_empty());
+    }
+
+    #[test]
+    fn markdown_code_blocks() {
+        let source = "# Usage\n\n```tsx title=\"App.tsx\"\nimport b from 'b';\n```\n\n- item\n\n  ~~~js\n  import a from 'a';\n  ~~~\n\n```sh\nimport x\n```\n\n````md\n```js\nimport c from 'c';\n```\n````\n";
+
+        assert_eq!(
+            scripts(Format::Markdown { code_blocks: true }, source),
+            vec!["import b from 'b';\n", "  import a from 'a';\n"]
+        );
+        assert!(scripts(Format::Markdown { code_blocks: false }, source).is_empty());
+    }
+
+    #[test]
+    fn skip_other_languages() {
+        let source = "<!-- <script>import a from 'a';</script> -->\n<script lang=\"coffee\">\nimport b from 'b'\n</script>\n<script src=\"./c.js\"></script>\n<scripts>\n";
+
+        assert!(scripts(Format::Vue, source).is_empty());
+    }
+
+    #[test]
+    fn attributes() {
+        assert_eq!(attribute("<script setup lang=\"ts\"", "lang"), Some("ts"));
+        assert_eq!(attribute("<script lang='tsx' setup", "lang"), Some("tsx"));
+        assert_eq!(attribute("<script lang=js>", "lang"), Some("js"));
+        assert_eq!(attribute("<script xlang=\"ts\"", "lang"), None);
+        assert_eq!(attribute("<script setup", "lang"), None);
+    }
+
+    #[test]
+    fn indentation_round_trip() {
+        let script = "  import a from 'a';\n\n    foo();\n";
+
+        assert_eq!(indentation(script), "  ");
+        assert_eq!(dedent(script, "  "), "import a from 'a';\n\n  foo();\n");
+        assert_eq!(indent(&dedent(script, "  "), "  "), script);
+        assert_eq!(indentation("  a;\nb;\n"), "");
+    }
+}
@@ -0,0 +1,157 @@
+//! Tracks strings, template literals and comments across the lines of a script.
+//!
+//! This is not a tokenizer: it only tells whether a line starts in top-level code, see
+//! [Lexer::in_code].
+
+use std::{iter::Peekable, str::Chars};
+
+/// A string or comment being read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// A string delimited by the given quote.
+    String(char),
+    LineComment,
+    BlockComment,
+}
+
+/// The nesting of template literals and of the code of their `${}` substitutions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// Code, with the number of braces opened in it.
+    Code {
+        braces: usize,
+    },
+    Template,
+}
+
+/// Follows the state of a script, as it is read.
+///
+/// Regular expression literals are not recognized, quotes in them may mislead the lexer.
+#[derive(Debug)]
+pub(crate) struct Lexer {
+    token: Option<Token>,
+    contexts: Vec<Context>,
+}
+
+impl Default for Lexer {
+    fn default() -> Self {
+        Self {
+            token: None,
+            contexts: vec![Context::Code { braces: 0 }],
+        }
+    }
+}
+
+impl Lexer {
+    /// Tells whether the lexer is in top-level code, outside of any string, template literal or
+    /// comment.
+    pub(crate) fn in_code(&self) -> bool {
+        self.token.is_none() && self.contexts.len() == 1
+    }
+
+    /// Skips the character escaped by a backslash, a `\r\n` line continuation being one.
+    fn escape(chars: &mut Peekable<Chars>) {
+        if chars.next() == Some('\r') {
+            chars.next_if_eq(&'\n');
+        }
+    }
+
+    /// Reads `text`, which follows the text read so far.
+    pub(crate) fn read(&mut self, text: &str) {
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let in_substitution = self.contexts.len() > 1;
+
+            match (self.token, self.contexts.last_mut()) {
+                (Some(Token::String(quote)), _) => match c {
+                    '\\' => Self::escape(&mut chars),
+                    // Strings can't span lines, unless escaped
+                    '\n' => self.token = None,
+                    c if c == quote => self.token = None,
+                    _ => {}
+                },
+                (Some(Token::LineComment), _) => {
+                    if c == '\n' {
+                        self.token = None;
+                    }
+                }
+                (Some(Token::BlockComment), _) => {
+                    if c == '*' && chars.next_if_eq(&'/').is_some() {
+                        self.token = None;
+                    }
+                }
+                (None, Some(Context::Template)) => match c {
+                    '\\' => Self::escape(&mut chars),
+                    '`' => {
+                        self.contexts.pop();
+                    }
+                    '$' if chars.next_if_eq(&'{').is_some() => {
+                        self.contexts.push(Context::Code { braces: 0 })
+                    }
+                    _ => {}
+                },
+                (None, Some(Context::Code { braces })) => match c {
+                    '\'' | '"' => self.token = Some(Token::String(c)),
+                    '`' => self.contexts.push(Context::Template),
+                    '/' if chars.next_if_eq(&'/').is_some() => {
+                        self.token = Some(Token::LineComment)
+                    }
+                    '/' if chars.next_if_eq(&'*').is_some() => {
+                        self.token = Some(Token::BlockComment)
+                    }
+                    '{' => *braces += 1,
+                    // The end of a template substitution
+                    '}' if *braces == 0 && in_substitution => {
+                        self.contexts.pop();
+                    }
+                    '}' => *braces = braces.saturating_sub(1),
+                    _ => {}
+                },
+                (None, None) => unreachable!("the top-level code context is never left"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_code(text: &str) -> bool {
+        let mut lexer = Lexer::default();
+        lexer.read(text);
+        lexer.in_code()
+    }
+
+    #[test]
+    fn code() {
+        assert!(in_code("const a = 'a';\n"));
+        assert!(in_code("const a = \"it's\"; // it's\n"));
+        assert!(in_code("/* a */ const b = `${'`'}`;\n"));
+        assert!(in_code("function f() {\n  return `${{ a: 1 }.a}`;\n}\n"));
+        assert!(in_code("const a = 'unterminated\n"));
+    }
+
+    #[test]
+    fn template_literal() {
+        assert!(!in_code("const a = `\n"));
+        assert!(!in_code("const a = `${b}\n"));
+        assert!(!in_code("const a = `${\n"));
+        assert!(!in_code("const a = `${`\n"));
+        assert!(!in_code("const a = `\\`\n"));
+    }
+
+    #[test]
+    fn comments() {
+        assert!(!in_code("/*\n"));
+        assert!(!in_code("/* a */ /** b\n"));
+        assert!(in_code("// /*\n"));
+    }
+
+    #[test]
+    fn multi_line_string() {
+        assert!(!in_code("const a = 'line \\\n"));
+        assert!(!in_code("const a = \"line \\\r\n"));
+    }
+}
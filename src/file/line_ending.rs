@@ -0,0 +1,79 @@
+/// The line ending style used in a file.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum LineEnding {
+    /// Unix style line ending, `\n`.
+    #[default]
+    Lf,
+    /// Windows style line ending, `\r\n`.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Detects the dominant line ending of a text.
+    ///
+    /// Defaults to [LineEnding::Lf] when there is no line ending or when there is a tie.
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+
+        if crlf > lf {
+            Self::CrLf
+        } else {
+            Self::Lf
+        }
+    }
+
+    /// The line ending characters.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+
+    /// Converts every line ending of `text` to this line ending.
+    pub fn apply(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n");
+
+        match self {
+            Self::Lf => text,
+            Self::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_empty() {
+        assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+    }
+
+    #[test]
+    fn detect_lf() {
+        assert_eq!(LineEnding::detect("a\nb\n"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn detect_crlf() {
+        assert_eq!(LineEnding::detect("a\r\nb\r\n"), LineEnding::CrLf);
+    }
+
+    #[test]
+    fn detect_dominant() {
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect("a\nb\nc\r\n"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn apply_crlf() {
+        assert_eq!(LineEnding::CrLf.apply("a\nb\r\n"), "a\r\nb\r\n");
+    }
+
+    #[test]
+    fn apply_lf() {
+        assert_eq!(LineEnding::Lf.apply("a\r\nb\n"), "a\nb\n");
+    }
+}
@@ -0,0 +1,211 @@
+//! Git integration, used to select the files touched by a change.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::Error;
+
+/// Which files to take from git.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Files with staged or unstaged changes, and untracked files.
+    Changed,
+    /// Files with staged changes.
+    ///
+    /// Their content is read from the index rather than from the working tree.
+    Staged,
+    /// Files that differ from the given revision.
+    Since(String),
+}
+
+/// Returns the existing files selected from the git repository containing `path`.
+///
+/// Paths are absolute.
+pub fn files(path: &Path, selection: &Selection) -> Result<Vec<PathBuf>, Error> {
+    let root = PathBuf::from(git(path, &["rev-parse", "--show-toplevel"])?.trim_end());
+
+    let outputs = match selection {
+        Selection::Changed => vec![
+            git(path, &["diff", "--name-only", "-z", "--diff-filter=ACMR"])?,
+            git(
+                path,
+                &[
+                    "diff",
+                    "--cached",
+                    "--name-only",
+                    "-z",
+                    "--diff-filter=ACMR",
+                ],
+            )?,
+            git(
+                path,
+                &[
+                    "ls-files",
+                    "--others",
+                    "--exclude-standard",
+                    "--full-name",
+                    "-z",
+                ],
+            )?,
+        ],
+        Selection::Staged => vec![git(
+            path,
+            &[
+                "diff",
+                "--cached",
+                "--name-only",
+                "-z",
+                "--diff-filter=ACMR",
+            ],
+        )?],
+        Selection::Since(rev) => vec![git(
+            path,
+            &[
+                "diff",
+                "--name-only",
+                "-z",
+                "--diff-filter=ACMR",
+                // The revision is never read as an option
+                "--end-of-options",
+                rev,
+                "--",
+            ],
+        )?],
+    };
+
+    let files: BTreeSet<_> = outputs
+        .iter()
+        .flat_map(|output| output.split('\0'))
+        .filter(|name| !name.is_empty())
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+
+    Ok(files.into_iter().collect())
+}
+
+/// Reads the staged content of a file.
+pub fn read_staged(path: &Path) -> Result<String, Error> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().ok_or("invalid file path")?;
+
+    git(dir, &["show", &format!(":./{}", name.to_string_lossy())])
+}
+
+/// Runs a git command in `dir` and returns its output.
+fn git(dir: &Path, args: &[&str]) -> Result<String, Error> {
+    let dir = if dir.is_file() {
+        dir.parent().unwrap_or(Path::new("."))
+    } else {
+        dir
+    };
+
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+
+    if !output.status.success() {
+        return Err(Error::Jsort {
+            err: format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ),
+        });
+    }
+
+    String::from_utf8(output.stdout).map_err(|_| "git output is not valid UTF-8".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+
+        for args in [
+            vec!["init", "-q"],
+            vec!["config", "user.email", "test@example.com"],
+            vec!["config", "user.name", "test"],
+        ] {
+            git(dir.path(), &args).unwrap();
+        }
+
+        fs::write(dir.path().join("committed.js"), "import a from 'a';\n").unwrap();
+        fs::write(dir.path().join("modified.js"), "import a from 'a';\n").unwrap();
+        git(dir.path(), &["add", "."]).unwrap();
+        git(dir.path(), &["commit", "-q", "-m", "init"]).unwrap();
+
+        dir
+    }
+
+    fn names(files: Vec<PathBuf>) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn changed() {
+        let dir = repo();
+        fs::write(dir.path().join("modified.js"), "import b from 'b';\n").unwrap();
+        fs::write(dir.path().join("untracked.js"), "").unwrap();
+
+        let files = files(dir.path(), &Selection::Changed).unwrap();
+
+        assert_eq!(names(files), vec!["modified.js", "untracked.js"]);
+    }
+
+    #[test]
+    fn staged() {
+        let dir = repo();
+        fs::write(dir.path().join("modified.js"), "import b from 'b';\n").unwrap();
+        fs::write(dir.path().join("staged.js"), "").unwrap();
+        git(dir.path(), &["add", "staged.js"]).unwrap();
+
+        let files = files(dir.path(), &Selection::Staged).unwrap();
+
+        assert_eq!(names(files), vec!["staged.js"]);
+    }
+
+    #[test]
+    fn since() {
+        let dir = repo();
+        fs::write(dir.path().join("modified.js"), "import b from 'b';\n").unwrap();
+        git(dir.path(), &["commit", "-q", "-am", "modify"]).unwrap();
+
+        let files = files(dir.path(), &Selection::Since("HEAD~1".to_owned())).unwrap();
+
+        assert_eq!(names(files), vec!["modified.js"]);
+    }
+
+    #[test]
+    fn since_option_like_revision() {
+        let dir = repo();
+        let output = dir.path().join("output");
+
+        let since = Selection::Since(format!("--output={}", output.display()));
+
+        assert!(files(dir.path(), &since).is_err());
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn read_staged_content() {
+        let dir = repo();
+        let path = dir.path().join("modified.js");
+        fs::write(&path, "import b from 'b';\n").unwrap();
+        git(dir.path(), &["add", "modified.js"]).unwrap();
+        fs::write(&path, "import c from 'c';\n").unwrap();
+
+        assert_eq!(read_staged(&path).unwrap(), "import b from 'b';\n");
+    }
+}
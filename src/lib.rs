@@ -10,16 +10,26 @@
 //! | Feature | Description |
 //! |---|---|
 //! | `argh` | *Enabled by default.* Enable command line argument parsing with [argh](https://github.com/google/argh). |
+//! | `watch` | *Enabled by default.* Enable the `--watch` mode, see [App::watch](app::App::watch). |
+//! | `lsp` | Enable the `jisort lsp` language server, see the [lsp] module. |
 
 pub mod app;
+mod cache;
 mod config;
 pub mod error;
 pub mod file;
+mod git;
+#[cfg(feature = "lsp")]
+pub mod lsp;
 mod parser;
 pub mod prelude;
+mod project;
+mod sorter;
 mod statements;
 
-pub use config::Config;
+#[cfg(feature = "lsp")]
+pub use config::{Command, LspCommand};
+pub use config::{Config, ModuleOrder, NodePrefix, RelativeOrder};
 use error::Error;
 
 use crate::app::report::{Report, ReportTrait};
@@ -36,6 +46,11 @@ pub fn run(config: Config) -> Result<(), Error> {
         return Ok(());
     }
 
+    #[cfg(feature = "lsp")]
+    if let Some(Command::Lsp(_)) = config.command {
+        return lsp::serve(config);
+    }
+
     let mut app = app::App::new(config.clone());
 
     if config.list {
@@ -45,12 +60,19 @@ pub fn run(config: Config) -> Result<(), Error> {
         return Ok(());
     }
 
+    #[cfg(feature = "watch")]
+    if config.watch {
+        println!("{}", app.run()?);
+        return app.watch();
+    }
+
     let report = app.run()?;
 
     println!("{}", report);
 
     match report {
         Report::Check(report) if report.err() => Err("".into()),
+        Report::Fix(report) if !report.failed_files.is_empty() => Err("".into()),
         _ => Ok(()),
     }
 }
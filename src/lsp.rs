@@ -0,0 +1,894 @@
+//! Language Server Protocol server, for editor integration.
+//!
+//! Only available on the `lsp` feature, run it with `jisort lsp`.
+//!
+//! The server provides:
+//!
+//! - diagnostics on files which imports are not sorted, see [File::check]
+//! - a *Sort imports* code action, also available as `source.organizeImports`
+//! - document & range formatting of the imports
+//!
+//! The settings of the projects are cached, and read again when their `package.json` or
+//! `tsconfig.json` change, if the client can watch files.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as NotificationTrait, PublishDiagnostics,
+    },
+    request::{
+        CodeActionRequest, Formatting, RangeFormatting, RegisterCapability, Request as RequestTrait,
+    },
+    CodeAction, CodeActionKind, CodeActionOptions, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidChangeWatchedFilesRegistrationOptions,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentFormattingParams,
+    DocumentRangeFormattingParams, FileSystemWatcher, GlobPattern, InitializeParams, OneOf,
+    Position, PublishDiagnosticsParams, Range, Registration, RegistrationParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+    WorkspaceEdit,
+};
+
+use crate::{
+    file::{File, FileData, Format},
+    project::{Project, Projects},
+    sorter::Sorter,
+    Config, Error,
+};
+
+/// Name of the server, used as diagnostics source.
+const SOURCE: &str = "jisort";
+/// Globs of the config files the projects are read from.
+const PROJECT_FILES: [&str; 4] = [
+    "**/package.json",
+    "**/pnpm-workspace.yaml",
+    "**/tsconfig*.json",
+    "**/jsconfig*.json",
+];
+
+/// Runs the language server over stdio until the client exits.
+pub fn serve(config: Config) -> Result<(), Error> {
+    let (connection, io_threads) = Connection::stdio();
+
+    run(&connection, &config)?;
+    io_threads.join()?;
+
+    Ok(())
+}
+
+/// Runs the language server on a connection until the client exits.
+fn run(connection: &Connection, config: &Config) -> Result<(), Error> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+            code_action_kinds: Some(vec![
+                CodeActionKind::QUICKFIX,
+                CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+            ]),
+            ..Default::default()
+        })),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    let params: InitializeParams = from_value(connection.initialize(to_value(capabilities)?)?)?;
+
+    let mut server = Server {
+        connection,
+        config,
+        documents: HashMap::new(),
+        projects: RefCell::default(),
+    };
+
+    let can_watch = params
+        .capabilities
+        .workspace
+        .and_then(|workspace| workspace.did_change_watched_files)
+        .and_then(|watch| watch.dynamic_registration)
+        .unwrap_or(false);
+    if can_watch {
+        server.watch_project_files()?;
+    }
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                server.request(request)?;
+            }
+            Message::Notification(notification) => server.notification(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// The server state.
+struct Server<'a> {
+    connection: &'a Connection,
+    config: &'a Config,
+    /// Content of the opened documents.
+    documents: HashMap<Url, String>,
+    /// Settings of the projects of the documents.
+    projects: RefCell<Projects>,
+}
+
+impl Server<'_> {
+    fn request(&mut self, request: Request) -> Result<(), Error> {
+        let id = request.id.clone();
+
+        match request.method.as_str() {
+            CodeActionRequest::METHOD => {
+                let Some(params): Option<CodeActionParams> = self.params(&id, request.params)?
+                else {
+                    return Ok(());
+                };
+                self.respond(id, self.code_actions(&params))
+            }
+            Formatting::METHOD => {
+                let Some(params): Option<DocumentFormattingParams> =
+                    self.params(&id, request.params)?
+                else {
+                    return Ok(());
+                };
+                let edits = self.edit(&params.text_document.uri).map(|edit| vec![edit]);
+                self.respond(id, edits)
+            }
+            RangeFormatting::METHOD => {
+                let Some(params): Option<DocumentRangeFormattingParams> =
+                    self.params(&id, request.params)?
+                else {
+                    return Ok(());
+                };
+                let edits = self
+                    .edit(&params.text_document.uri)
+                    .filter(|edit| overlaps(&edit.range, &params.range))
+                    .map(|edit| vec![edit]);
+                self.respond(id, edits)
+            }
+            _ => self.send(Message::Response(Response::new_err(
+                id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("unknown request {}", request.method),
+            ))),
+        }
+    }
+
+    fn notification(&mut self, notification: Notification) -> Result<(), Error> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params): Option<DidOpenTextDocumentParams> =
+                    notification_params(notification)
+                else {
+                    return Ok(());
+                };
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.publish_diagnostics(uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params): Option<DidChangeTextDocumentParams> =
+                    notification_params(notification)
+                else {
+                    return Ok(());
+                };
+                let uri = params.text_document.uri;
+                // Full sync: the last change holds the whole document
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                self.publish_diagnostics(uri)
+            }
+            DidChangeWatchedFiles::METHOD => {
+                let Some(params): Option<DidChangeWatchedFilesParams> =
+                    notification_params(notification)
+                else {
+                    return Ok(());
+                };
+                if !params
+                    .changes
+                    .iter()
+                    .any(|change| is_project_file(&path(&change.uri)))
+                {
+                    return Ok(());
+                }
+
+                // The imports of every document may be classified differently
+                self.projects.borrow_mut().clear();
+                let uris: Vec<_> = self.documents.keys().cloned().collect();
+                for uri in uris {
+                    self.publish_diagnostics(uri)?;
+                }
+                Ok(())
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params): Option<DidCloseTextDocumentParams> =
+                    notification_params(notification)
+                else {
+                    return Ok(());
+                };
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.notify::<PublishDiagnostics>(PublishDiagnosticsParams {
+                    uri,
+                    diagnostics: vec![],
+                    version: None,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Asks the client to notify changes of the config files of the projects.
+    fn watch_project_files(&self) -> Result<(), Error> {
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: PROJECT_FILES
+                .iter()
+                .map(|glob| FileSystemWatcher {
+                    glob_pattern: GlobPattern::String(glob.to_string()),
+                    kind: None,
+                })
+                .collect(),
+        };
+        let params = RegistrationParams {
+            registrations: vec![Registration {
+                id: "jisort-project-files".to_owned(),
+                method: DidChangeWatchedFiles::METHOD.to_owned(),
+                register_options: Some(to_value(options)?),
+            }],
+        };
+
+        // The response is ignored
+        self.send(Message::Request(Request::new(
+            RequestId::from("jisort-project-files".to_owned()),
+            RegisterCapability::METHOD.to_owned(),
+            params,
+        )))
+    }
+
+    /// Returns the settings of the project containing a document.
+    fn project(&self, uri: &Url) -> Rc<Project> {
+        self.projects.borrow_mut().for_file(&path(uri))
+    }
+
+    /// Publishes a diagnostic when the imports of a document are not sorted.
+    fn publish_diagnostics(&self, uri: Url) -> Result<(), Error> {
+        let diagnostics = self
+            .documents
+            .get(&uri)
+            .and_then(|text| diagnostic(&path(&uri), text, self.config, self.project(&uri)))
+            .into_iter()
+            .collect();
+
+        self.notify::<PublishDiagnostics>(PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        })
+    }
+
+    /// Returns the *Sort imports* code actions of a document.
+    fn code_actions(&self, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let uri = &params.text_document.uri;
+        let Some(edit) = self.edit(uri) else {
+            return vec![];
+        };
+
+        let diagnostics: Vec<_> = params
+            .context
+            .diagnostics
+            .iter()
+            .filter(|d| d.source.as_deref() == Some(SOURCE))
+            .cloned()
+            .collect();
+
+        [
+            CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+            CodeActionKind::QUICKFIX,
+        ]
+        .into_iter()
+        .filter(|kind| {
+            params
+                .context
+                .only
+                .as_ref()
+                .is_none_or(|only| only.iter().any(|o| kind.as_str().starts_with(o.as_str())))
+        })
+        .map(|kind| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Sort imports".to_owned(),
+                diagnostics: (kind == CodeActionKind::QUICKFIX).then(|| diagnostics.clone()),
+                kind: Some(kind),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit.clone()])])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        })
+        .collect()
+    }
+
+    /// Returns the edit sorting the imports of a document, if they need to be.
+    fn edit(&self, uri: &Url) -> Option<TextEdit> {
+        sort_edit(
+            &path(uri),
+            self.documents.get(uri)?,
+            self.config,
+            self.project(uri),
+        )
+    }
+
+    /// Reads the params of a request, answering it with an error if they are invalid.
+    fn params<T>(&self, id: &RequestId, params: serde_json::Value) -> Result<Option<T>, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match serde_json::from_value(params) {
+            Ok(params) => Ok(Some(params)),
+            Err(err) => {
+                self.send(Message::Response(Response::new_err(
+                    id.clone(),
+                    lsp_server::ErrorCode::InvalidParams as i32,
+                    err.to_string(),
+                )))?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn respond<R>(&self, id: RequestId, result: R) -> Result<(), Error>
+    where
+        R: serde::Serialize,
+    {
+        self.send(Message::Response(Response::new_ok(id, result)))
+    }
+
+    fn notify<N>(&self, params: N::Params) -> Result<(), Error>
+    where
+        N: NotificationTrait,
+    {
+        self.send(Message::Notification(Notification::new(
+            N::METHOD.to_owned(),
+            params,
+        )))
+    }
+
+    fn send(&self, message: Message) -> Result<(), Error> {
+        self.connection
+            .sender
+            .send(message)
+            .map_err(|_| "language server connection closed".into())
+    }
+}
+
+/// Reads the params of a notification, which is ignored with a warning if they are invalid.
+fn notification_params<T>(notification: Notification) -> Option<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    serde_json::from_value(notification.params)
+        .map_err(|err| {
+            eprintln!(
+                "\x1b[33mjisort warning:\x1b[m invalid {} params: {}, ignored",
+                notification.method, err
+            )
+        })
+        .ok()
+}
+
+/// Returns the diagnostic of a document which imports are not sorted.
+fn diagnostic(
+    path: &Path,
+    text: &str,
+    config: &Config,
+    project: Rc<Project>,
+) -> Option<Diagnostic> {
+    let data = parse(path, text, config, project);
+
+    if File::new(path).check(&data) {
+        return None;
+    }
+
+    let lines: Vec<_> = data
+        .scripts()
+        .flat_map(|script| script.import_lines.iter().copied())
+        .collect();
+    let first_line = lines.iter().min().copied().unwrap_or(1);
+    let end = if data.format.is_container() {
+        // End of the line of the last import
+        let last_line = lines.iter().max().copied().unwrap_or(1);
+        let line_start = text
+            .match_indices('\n')
+            .nth(last_line.saturating_sub(2))
+            .map_or(0, |(i, _)| i + 1);
+        line_start
+            + text[line_start..]
+                .find('\n')
+                .unwrap_or(text.len() - line_start)
+    } else {
+        text.len() - data.rest.len()
+    };
+    let header = &text[..end];
+
+    let message = if data.is_dangerous() {
+        "Imports are not sorted. Sorting is dangerous because of comments between imports."
+    } else {
+        "Imports are not sorted."
+    };
+
+    Some(Diagnostic {
+        range: Range::new(
+            Position::new(first_line as u32 - 1, 0),
+            position(text, header.trim_end().len()),
+        ),
+        severity: Some(DiagnosticSeverity::WARNING),
+        source: Some(SOURCE.to_owned()),
+        message: message.to_owned(),
+        ..Default::default()
+    })
+}
+
+/// Returns the edit replacing the imports of `text` with the sorted ones.
+///
+/// Like [App::fix](crate::App::fix), dangerous documents are left untouched unless
+/// [Config::force] is set, and the sorted content must pass the round-trip verification.
+fn sort_edit(path: &Path, text: &str, config: &Config, project: Rc<Project>) -> Option<TextEdit> {
+    let data = parse(path, text, config, project);
+
+    if data.is_dangerous() && !config.force {
+        return None;
+    }
+
+    let content = File::render(&data);
+    File::verify(&data, &content).ok()?;
+
+    // Only the changed lines are replaced, hoisted imports changing the rest of scripts too
+    let start = common_prefix(text, &content);
+    let suffix = common_suffix(&text[start..], &content[start..]);
+
+    let old_header = &text[start..text.len() - suffix];
+    let new_header = &content[start..content.len() - suffix];
+
+    (old_header != new_header).then(|| TextEdit {
+        range: Range::new(
+            position(text, start),
+            position(text, start + old_header.len()),
+        ),
+        new_text: new_header.to_owned(),
+    })
+}
+
+/// Parses a document, with the settings of the project containing it.
+fn parse(path: &Path, text: &str, config: &Config, project: Rc<Project>) -> FileData {
+    File::parse_with(text, Format::of(path, config), Sorter::new(config, project))
+}
+
+/// Tells whether a file is a config file the projects are read from, see [PROJECT_FILES].
+fn is_project_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+
+    matches!(name, "package.json" | "pnpm-workspace.yaml")
+        || ((name.starts_with("tsconfig") || name.starts_with("jsconfig"))
+            && name.ends_with(".json"))
+}
+
+/// Converts a byte offset into a LSP position, which character is counted in UTF-16 code units.
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character = before[line_start..].encode_utf16().count();
+
+    Position::new(line as u32, character as u32)
+}
+
+/// Returns the length of the common prefix of two texts, at line boundaries.
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.split_inclusive('\n')
+        .zip(b.split_inclusive('\n'))
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len())
+        .sum()
+}
+
+/// Returns the length of the common suffix of two texts, at line boundaries.
+fn common_suffix(a: &str, b: &str) -> usize {
+    a.split_inclusive('\n')
+        .rev()
+        .zip(b.split_inclusive('\n').rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len())
+        .sum()
+}
+
+/// Tells whether two ranges overlap.
+fn overlaps(a: &Range, b: &Range) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+/// Converts a document uri into a path.
+fn path(uri: &Url) -> PathBuf {
+    uri.to_file_path()
+        .unwrap_or_else(|_| PathBuf::from(uri.path()))
+}
+
+fn to_value<T>(value: T) -> Result<serde_json::Value, Error>
+where
+    T: serde::Serialize,
+{
+    serde_json::to_value(value).map_err(|e| Error::Jsort { err: e.to_string() })
+}
+
+fn from_value<T>(value: serde_json::Value) -> Result<T, Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    serde_json::from_value(value).map_err(|e| Error::Jsort { err: e.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, thread};
+
+    use lsp_types::{
+        notification::{Exit, Initialized},
+        request::{Initialize, Shutdown},
+        ClientCapabilities, CodeActionContext, DidChangeWatchedFilesClientCapabilities,
+        DocumentFormattingParams, FileChangeType, FileEvent, FormattingOptions, InitializedParams,
+        PartialResultParams, TextDocumentContentChangeEvent, TextDocumentIdentifier,
+        TextDocumentItem, VersionedTextDocumentIdentifier, WorkDoneProgressParams,
+        WorkspaceClientCapabilities,
+    };
+
+    use super::*;
+
+    const UNSORTED: &str = "import b from './b';\nimport a from 'a';\n\nfoo();\n";
+    const SORTED: &str = "import a from 'a';\n\nimport b from './b';\n";
+
+    /// An in-process LSP client.
+    struct Client {
+        connection: Connection,
+        next_id: i32,
+    }
+
+    impl Client {
+        /// Starts a server and initializes it.
+        fn start() -> (Self, thread::JoinHandle<()>) {
+            Self::start_with(InitializeParams::default())
+        }
+
+        /// Starts a server and initializes it with `params`.
+        fn start_with(params: InitializeParams) -> (Self, thread::JoinHandle<()>) {
+            let (server, connection) = Connection::memory();
+            let handle = thread::spawn(move || run(&server, &Config::default()).unwrap());
+
+            let mut client = Self {
+                connection,
+                next_id: 0,
+            };
+            client.request::<Initialize>(params);
+            client.notify::<Initialized>(InitializedParams {});
+
+            (client, handle)
+        }
+
+        fn request<R>(&mut self, params: R::Params) -> R::Result
+        where
+            R: RequestTrait,
+        {
+            self.next_id += 1;
+            let request = Request::new(self.next_id.into(), R::METHOD.to_owned(), params);
+            self.connection.sender.send(request.into()).unwrap();
+
+            loop {
+                if let Message::Response(response) = self.connection.receiver.recv().unwrap() {
+                    return serde_json::from_value(response.result.unwrap()).unwrap();
+                }
+            }
+        }
+
+        fn notify<N>(&self, params: N::Params)
+        where
+            N: NotificationTrait,
+        {
+            let notification = Notification::new(N::METHOD.to_owned(), params);
+            self.connection.sender.send(notification.into()).unwrap();
+        }
+
+        fn diagnostics(&self) -> PublishDiagnosticsParams {
+            loop {
+                if let Message::Notification(notification) =
+                    self.connection.receiver.recv().unwrap()
+                {
+                    if notification.method == PublishDiagnostics::METHOD {
+                        return serde_json::from_value(notification.params).unwrap();
+                    }
+                }
+            }
+        }
+
+        fn open(&self, uri: &Url, text: &str) {
+            self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(
+                    uri.clone(),
+                    "javascript".to_owned(),
+                    1,
+                    text.to_owned(),
+                ),
+            });
+        }
+
+        fn stop(mut self, handle: thread::JoinHandle<()>) {
+            self.request::<Shutdown>(());
+            self.notify::<Exit>(());
+            handle.join().unwrap();
+        }
+    }
+
+    fn uri() -> Url {
+        Url::parse("file:///project/index.js").unwrap()
+    }
+
+    #[test]
+    fn diagnostics_on_unsorted_document() {
+        let (client, handle) = Client::start();
+
+        client.open(&uri(), UNSORTED);
+        let params = client.diagnostics();
+
+        assert_eq!(params.uri, uri());
+        assert_eq!(params.diagnostics.len(), 1);
+        assert_eq!(params.diagnostics[0].range.start, Position::new(0, 0));
+        assert_eq!(params.diagnostics[0].range.end, Position::new(1, 18));
+
+        client.stop(handle);
+    }
+
+    #[test]
+    fn no_diagnostics_on_sorted_document() {
+        let (client, handle) = Client::start();
+
+        client.open(&uri(), SORTED);
+
+        assert!(client.diagnostics().diagnostics.is_empty());
+
+        client.stop(handle);
+    }
+
+    #[test]
+    fn formatting() {
+        let (mut client, handle) = Client::start();
+        client.open(&uri(), UNSORTED);
+
+        let edits = client
+            .request::<Formatting>(DocumentFormattingParams {
+                text_document: TextDocumentIdentifier::new(uri()),
+                options: FormattingOptions::default(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            })
+            .unwrap();
+
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.end, Position::new(2, 0));
+        assert_eq!(
+            edits[0].new_text,
+            "import a from 'a';\n\nimport b from './b';\n"
+        );
+
+        client.stop(handle);
+    }
+
+    #[test]
+    fn organize_imports_action() {
+        let (mut client, handle) = Client::start();
+        client.open(&uri(), UNSORTED);
+
+        let actions = client
+            .request::<CodeActionRequest>(CodeActionParams {
+                text_document: TextDocumentIdentifier::new(uri()),
+                range: Range::default(),
+                context: CodeActionContext {
+                    diagnostics: vec![],
+                    only: Some(vec![CodeActionKind::SOURCE_ORGANIZE_IMPORTS]),
+                    trigger_kind: None,
+                },
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            })
+            .unwrap();
+
+        assert_eq!(actions.len(), 1);
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        assert_eq!(action.kind, Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS));
+
+        client.stop(handle);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let (client, handle) = Client::start();
+
+        // Ignored, the server keeps running
+        let notification = Notification::new(DidOpenTextDocument::METHOD.to_owned(), 1);
+        client.connection.sender.send(notification.into()).unwrap();
+
+        let request = Request::new(0.into(), Formatting::METHOD.to_owned(), 1);
+        client.connection.sender.send(request.into()).unwrap();
+        let Message::Response(response) = client.connection.receiver.recv().unwrap() else {
+            panic!("expected a response");
+        };
+        assert_eq!(
+            response.error.unwrap().code,
+            lsp_server::ErrorCode::InvalidParams as i32
+        );
+
+        client.open(&uri(), UNSORTED);
+        assert_eq!(client.diagnostics().diagnostics.len(), 1);
+
+        client.stop(handle);
+    }
+
+    #[test]
+    fn register_project_files_watchers() {
+        let (client, handle) = Client::start_with(InitializeParams {
+            capabilities: ClientCapabilities {
+                workspace: Some(WorkspaceClientCapabilities {
+                    did_change_watched_files: Some(DidChangeWatchedFilesClientCapabilities {
+                        dynamic_registration: Some(true),
+                        relative_pattern_support: None,
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let Message::Request(request) = client.connection.receiver.recv().unwrap() else {
+            panic!("expected a request");
+        };
+        assert_eq!(request.method, RegisterCapability::METHOD);
+
+        client.stop(handle);
+    }
+
+    #[test]
+    fn project_files_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = Url::from_file_path(dir.path().join("index.js")).unwrap();
+        let text = "import a from 'acme';\nimport b from 'bar';\n";
+        let (client, handle) = Client::start();
+
+        client.open(&uri, text);
+        assert!(client.diagnostics().diagnostics.is_empty());
+
+        // `acme` is now a workspace package, grouped after the modules
+        let manifest = dir.path().join("package.json");
+        fs::write(&manifest, r#"{ "name": "acme" }"#).unwrap();
+
+        // The project is cached
+        client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: text.to_owned(),
+            }],
+        });
+        assert!(client.diagnostics().diagnostics.is_empty());
+
+        client.notify::<DidChangeWatchedFiles>(DidChangeWatchedFilesParams {
+            changes: vec![FileEvent::new(
+                Url::from_file_path(&manifest).unwrap(),
+                FileChangeType::CREATED,
+            )],
+        });
+        let params = client.diagnostics();
+
+        assert_eq!(params.uri, uri);
+        assert_eq!(params.diagnostics.len(), 1);
+
+        client.stop(handle);
+    }
+
+    #[test]
+    fn project_files() {
+        assert!(is_project_file(Path::new("/app/package.json")));
+        assert!(is_project_file(Path::new("/app/tsconfig.base.json")));
+        assert!(is_project_file(Path::new("jsconfig.json")));
+        assert!(!is_project_file(Path::new("/app/index.json")));
+        assert!(!is_project_file(Path::new("/app/tsconfig.ts")));
+    }
+
+    #[test]
+    fn no_edit_on_dangerous_document() {
+        let text = "import b from 'b';\n// comment\nimport a from 'a';\n";
+
+        let path = Path::new("index.js");
+
+        assert!(sort_edit(path, text, &Config::default(), Default::default()).is_none());
+        assert!(sort_edit(
+            path,
+            text,
+            &Config {
+                force: true,
+                ..Default::default()
+            },
+            Default::default()
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn edit_vue_script() {
+        let text = "<template>\n  <div />\n</template>\n<script setup>\nimport b from 'b';\nimport a from 'a';\n</script>\n";
+
+        let edit = sort_edit(
+            Path::new("App.vue"),
+            text,
+            &Config::default(),
+            Default::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            edit.range,
+            Range::new(Position::new(4, 0), Position::new(6, 0))
+        );
+        assert_eq!(edit.new_text, "import a from 'a';\nimport b from 'b';\n");
+
+        let diagnostic = diagnostic(
+            Path::new("App.vue"),
+            text,
+            &Config::default(),
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            diagnostic.range,
+            Range::new(Position::new(4, 0), Position::new(5, 18))
+        );
+    }
+
+    #[test]
+    fn edit_with_hoisted_imports() {
+        let text = "import b from 'b';\n\nconst x = 'é';\nimport a from 'a';\n";
+        let config = Config {
+            hoist: true,
+            ..Default::default()
+        };
+
+        let edit = sort_edit(Path::new("index.js"), text, &config, Default::default()).unwrap();
+
+        assert_eq!(
+            edit.range,
+            Range::new(Position::new(0, 0), Position::new(4, 0))
+        );
+        assert_eq!(
+            edit.new_text,
+            "import a from 'a';\nimport b from 'b';\n\nconst x = 'é';\n"
+        );
+    }
+
+    #[test]
+    fn position_in_utf16() {
+        assert_eq!(position("ab\ncé😀d", 10), Position::new(1, 4));
+    }
+}
@@ -1,7 +1,9 @@
 pub mod comment;
+mod directive;
 mod empty_line;
 mod import;
 
 pub use comment::comment;
+pub use directive::triple_slash_directive;
 pub use empty_line::*;
 pub use import::*;
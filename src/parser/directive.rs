@@ -0,0 +1,44 @@
+use nom::{
+    bytes::complete::{is_not, tag},
+    character::complete::space0,
+    combinator::{peek, recognize},
+    sequence::pair,
+    IResult,
+};
+
+use crate::statements::TripleSlashDirective;
+
+/// Matches a triple-slash directive, like `/// <reference types="node" />`.
+pub fn triple_slash_directive(input: &str) -> IResult<&str, TripleSlashDirective> {
+    let (input, _) = space0(input)?;
+    let (input, _) = tag("///")(input)?;
+    let (input, _) = peek(pair(space0, tag("<")))(input)?;
+    let (input, data) = recognize(is_not("\n\r"))(input)?;
+
+    Ok((
+        input,
+        TripleSlashDirective {
+            data: data.to_owned(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference() {
+        let (rest, directive) =
+            triple_slash_directive("/// <reference types=\"vite/client\" />\n").unwrap();
+
+        assert_eq!(rest, "\n");
+        assert_eq!(directive.data, " <reference types=\"vite/client\" />");
+    }
+
+    #[test]
+    fn not_a_directive() {
+        assert!(triple_slash_directive("/// a doc comment").is_err());
+        assert!(triple_slash_directive("// <reference />").is_err());
+    }
+}
@@ -1,45 +1,176 @@
 use nom::{
     branch::alt,
-    bytes::complete::{escaped, tag, take_until1},
-    character::complete::{char, none_of, one_of, space0, space1},
-    combinator::{cond, opt, peek},
-    sequence::delimited,
+    bytes::complete::{is_not, tag, take_while1, take_while_m_n},
+    character::complete::{char, hex_digit1, line_ending, satisfy, space0, space1},
+    combinator::{cond, eof, not, opt, peek, recognize, verify},
+    error::{make_error, ErrorKind},
+    multi::many0_count,
+    sequence::{delimited, pair, preceded, terminated},
     IResult,
 };
 
-use crate::statements::ImportStatement;
+use crate::statements::{ImportKind, ImportStatement, NamespaceAlias, TypeOnly};
 
 use super::comment::comment;
 
-/// Tries to match a JS string.
+/// Tries to match a JS string literal, as found in module specifiers.
 ///
-/// This function does not match a real JS string,
-/// but rather a string that can take place in an import statement.
+/// Returns the content of the string as written in the source, escape sequences included,
+/// so that it is written back unaltered.
+/// Template literals are not matched, as they are not valid module specifiers.
 ///
 /// # Example
 ///
 /// - 'foo'
 /// - "bar"
+/// - '\u{e9}t\xe9'
 fn js_string(input: &str) -> IResult<&str, &str> {
-    let (input, string) = delimited(
-        alt((char('\''), char('"'))),
-        escaped(none_of(r#"\"'"#), '\\', one_of(r#"'"n\"#)),
-        alt((char('\''), char('"'))),
-    )(input)?;
+    alt((
+        delimited(char('\''), string_characters('\''), char('\'')),
+        delimited(char('"'), string_characters('"'), char('"')),
+    ))(input)
+}
+
+/// Matches the characters of a string delimited by `quote`, up to the closing quote.
+fn string_characters(quote: char) -> impl FnMut(&str) -> IResult<&str, &str> {
+    move |input| {
+        recognize(many0_count(alt((
+            take_while1(|c| c != quote && !matches!(c, '\\' | '\n' | '\r')),
+            preceded(char('\\'), escape_sequence),
+        ))))(input)
+    }
+}
+
+/// Matches what follows a backslash in a string: an escape sequence or a line continuation.
+///
+/// Legacy octal escapes are rejected, as modules are strict mode code.
+fn escape_sequence(input: &str) -> IResult<&str, &str> {
+    alt((
+        // line continuation
+        alt((
+            tag("\r\n"),
+            tag("\n"),
+            tag("\r"),
+            tag("\u{2028}"),
+            tag("\u{2029}"),
+        )),
+        // null character
+        recognize(pair(char('0'), not(satisfy(|c| c.is_ascii_digit())))),
+        // hexadecimal escape
+        recognize(pair(char('x'), hex_digits(2))),
+        // unicode escapes
+        recognize(delimited(
+            tag("u{"),
+            verify(hex_digit1, |hex: &str| {
+                let hex = hex.trim_start_matches('0');
+                hex.is_empty() || u32::from_str_radix(hex, 16).is_ok_and(|c| c <= 0x10FFFF)
+            }),
+            char('}'),
+        )),
+        recognize(pair(char('u'), hex_digits(4))),
+        // single escape characters, like `\n`, and other characters, escaped for nothing
+        recognize(satisfy(|c| {
+            !c.is_ascii_digit() && !matches!(c, 'x' | 'u' | '\n' | '\r' | '\u{2028}' | '\u{2029}')
+        })),
+    ))(input)
+}
+
+/// Matches exactly `n` hexadecimal digits.
+fn hex_digits(n: usize) -> impl FnMut(&str) -> IResult<&str, &str> {
+    move |input| take_while_m_n(n, n, |c: char| c.is_ascii_hexdigit())(input)
+}
+
+/// Tells whether `input` starts like the bindings of an import declaration: a name, `*` or `{`.
+///
+/// This tells declarations apart from expressions starting with `import`, like `import ('x')`.
+fn is_binding_start(input: &str) -> bool {
+    input.starts_with(|c: char| c.is_alphabetic() || "_$*{".contains(c))
+}
+
+/// Tells whether `c` can be part of a name.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Matches the bindings of an import declaration, up to the `from` keyword.
+///
+/// The keyword is a whole word outside of braces and after the first name, so that bindings like
+/// `fromage`, `{ from }` or a default import named `from` are not mistaken for it.
+fn bindings(input: &str) -> IResult<&str, &str> {
+    let mut braces = 0usize;
+    let mut previous = None;
+
+    for (i, c) in input.char_indices() {
+        match c {
+            '{' => braces += 1,
+            '}' => braces = braces.saturating_sub(1),
+            'f' if braces == 0
+                && i > 0
+                && input[i..].starts_with("from")
+                && !previous.is_some_and(is_name_char)
+                && !input[i + 4..].starts_with(is_name_char) =>
+            {
+                return Ok((&input[i..], &input[..i]));
+            }
+            _ => {}
+        }
+        previous = Some(c);
+    }
 
-    Ok((input, string))
+    Err(nom::Err::Error(make_error(input, ErrorKind::TakeUntil)))
 }
 
 /// Tries to parse a js import statement.
+///
+/// TypeScript `import x = require('x');` statements are imports too.
+/// Dynamic `import()` and `import.meta` are expressions, and are never parsed as imports.
 pub fn import_statement(input: &str) -> IResult<&str, ImportStatement> {
+    alt((import_require, import_from))(input)
+}
+
+/// Tries to parse the keyword of a type-only import, like `type` in `import type x from 'x';`.
+fn type_only(input: &str) -> IResult<&str, TypeOnly> {
+    let (input, keyword) = terminated(alt((tag("typeof"), tag("type"))), space1)(input)?;
+
+    let type_only = match keyword {
+        "typeof" => TypeOnly::Typeof,
+        _ => TypeOnly::Type,
+    };
+
+    Ok((input, type_only))
+}
+
+/// Tries to parse a `import x from 'x';` statement.
+///
+/// Type-only imports (`import type` and Flow's `import typeof`) are recognised, unless the keyword
+/// is the imported name, like in `import type from 'x';`.
+fn import_from(input: &str) -> IResult<&str, ImportStatement> {
     // import keyword
     let (input, _) = tag("import")(input)?;
     let (input, _) = space1(input)?;
 
+    match type_only(input) {
+        Ok((rest, type_only)) => import_from_identifiers(rest, Some(type_only))
+            .or_else(|_| import_from_identifiers(input, None)),
+        Err(_) => import_from_identifiers(input, None),
+    }
+}
+
+/// Parses the rest of a `import x from 'x';` statement, after the `import` keyword and the
+/// type-only keyword if any.
+fn import_from_identifiers(
+    input: &str,
+    type_only: Option<TypeOnly>,
+) -> IResult<&str, ImportStatement> {
     let direct_import = peek(js_string)(input).is_ok();
 
-    // identifier and from keyword
-    let (input, identifier) = cond(!direct_import, take_until1("from"))(input)?;
+    // identifier and from keyword, which never contains `=` nor `;`
+    let (input, identifier) = cond(
+        !direct_import,
+        verify(bindings, |s: &str| {
+            is_binding_start(s) && !s.contains(['=', ';'])
+        }),
+    )(input)?;
     let (input, _) = cond(identifier.is_some(), tag("from"))(input)?;
     let (input, _) = space0(input)?;
 
@@ -56,6 +187,77 @@ pub fn import_statement(input: &str) -> IResult<&str, ImportStatement> {
             identifiers: identifier.map(|s| s.trim().to_owned()),
             module: module.to_owned(),
             comment,
+            kind: ImportKind::Esm,
+            type_only,
+        },
+    ))
+}
+
+/// Tries to parse the start of a TypeScript import equals declaration, like `import x =` or
+/// `export import x =`.
+///
+/// Returns whether it is exported, its type-only keyword, and the imported name.
+fn import_equals(input: &str) -> IResult<&str, (bool, Option<TypeOnly>, &str)> {
+    let (input, export) = opt(pair(tag("export"), space1))(input)?;
+    let (input, _) = tag("import")(input)?;
+    let (input, _) = space1(input)?;
+    let (input, type_only) = opt(type_only)(input)?;
+    let (input, name) = verify(is_not("=;'\"\r\n"), is_binding_start)(input)?;
+    let (input, _) = char('=')(input)?;
+    let (input, _) = space0(input)?;
+
+    Ok((input, (export.is_some(), type_only, name.trim())))
+}
+
+/// Tries to parse a TypeScript `import x = require('x');` statement.
+fn import_require(input: &str) -> IResult<&str, ImportStatement> {
+    let (input, (export, type_only, name)) = import_equals(input)?;
+    let (input, _) = tag("require")(input)?;
+    let (input, module) = delimited(
+        pair(space0, pair(char('('), space0)),
+        js_string,
+        pair(space0, char(')')),
+    )(input)?;
+
+    // end
+    let (input, _) = opt(char(';'))(input)?;
+    let (input, comment) = opt(comment)(input)?;
+
+    Ok((
+        input,
+        ImportStatement {
+            identifiers: Some(name.to_owned()),
+            module: module.to_owned(),
+            comment,
+            kind: ImportKind::Require { export },
+            type_only,
+        },
+    ))
+}
+
+/// Tries to parse a TypeScript namespace alias, like `import Foo = Bar.Baz;`.
+pub fn namespace_alias(input: &str) -> IResult<&str, NamespaceAlias> {
+    let (input, (export, type_only, name)) = import_equals(input)?;
+    let (input, entity) = verify(
+        take_while1(|c: char| c.is_alphanumeric() || "_$.".contains(c)),
+        |entity: &str| entity != "require",
+    )(input)?;
+
+    // end, the alias must be alone on its line
+    let (input, _) = opt(char(';'))(input)?;
+    let (input, comment) = opt(comment)(input)?;
+    let (input, _) = peek(terminated(space0, alt((line_ending, eof))))(input)?;
+
+    Ok((
+        input,
+        NamespaceAlias {
+            export,
+            name: match type_only {
+                Some(type_only) => format!("{} {}", type_only.keyword(), name),
+                None => name.to_owned(),
+            },
+            entity: entity.to_owned(),
+            comment,
         },
     ))
 }
@@ -90,6 +292,52 @@ mod tests {
             assert_eq!(rest, "");
             assert_eq!(string, r#"f\'oo"#);
         }
+
+        #[test]
+        fn other_quote() {
+            assert_eq!(js_string(r#""it's""#).unwrap(), ("", "it's"));
+            assert_eq!(js_string(r#"'say "hi"'"#).unwrap(), ("", r#"say "hi""#));
+        }
+
+        #[test]
+        fn mismatched_quotes() {
+            assert!(js_string(r#"'foo""#).is_err());
+            assert!(js_string(r#""foo'"#).is_err());
+            assert!(js_string("'foo").is_err());
+            assert!(js_string("`foo`").is_err());
+        }
+
+        #[test]
+        fn escape_sequences() {
+            for string in [
+                r#"'\b\f\n\r\t\v\0\\\"\a'"#,
+                r#"'\x41\xfF'"#,
+                r#"'\u00e9\u00E9'"#,
+                r#"'\u{e9}\u{1F600}\u{0000000041}\u{10FFFF}'"#,
+                "'line\\\ncontinuation\\\r\ncrlf'",
+                "'\u{2028}'",
+            ] {
+                let (rest, raw) = js_string(string).unwrap();
+
+                assert_eq!(rest, "");
+                assert_eq!(raw, &string[1..string.len() - 1]);
+            }
+        }
+
+        #[test]
+        fn invalid_escape_sequences() {
+            for string in [
+                r#"'\x4'"#,
+                r#"'\u00e'"#,
+                r#"'\u{110000}'"#,
+                r#"'\u{}'"#,
+                r#"'\01'"#,
+                r#"'\8'"#,
+                "'new\nline'",
+            ] {
+                assert!(js_string(string).is_err(), "{string}");
+            }
+        }
     }
 
     mod import_statement {
@@ -106,7 +354,9 @@ mod tests {
                 ImportStatement {
                     identifiers: Some("x".to_owned()),
                     module: "a-module".to_owned(),
-                    comment: None
+                    comment: None,
+                    kind: ImportKind::Esm,
+                    type_only: None,
                 }
             )
         }
@@ -122,7 +372,9 @@ mod tests {
                 ImportStatement {
                     identifiers: Some("{ a, b }".to_owned()),
                     module: "a-module".to_owned(),
-                    comment: None
+                    comment: None,
+                    kind: ImportKind::Esm,
+                    type_only: None,
                 }
             )
         }
@@ -138,7 +390,9 @@ mod tests {
                 ImportStatement {
                     identifiers: None,
                     module: "style.css".to_owned(),
-                    comment: None
+                    comment: None,
+                    kind: ImportKind::Esm,
+                    type_only: None,
                 }
             )
         }
@@ -156,7 +410,9 @@ mod tests {
                 ImportStatement {
                     identifiers: None,
                     module: "style.css".to_owned(),
-                    comment: None
+                    comment: None,
+                    kind: ImportKind::Esm,
+                    type_only: None,
                 }
             )
         }
@@ -179,11 +435,109 @@ mod tests {
                     comment: Some(crate::statements::Comment {
                         data: " this is an import  ".to_owned(),
                         ty: crate::statements::CommentType::Single
-                    })
+                    }),
+                    kind: ImportKind::Esm,
+                    type_only: None,
                 }
             )
         }
 
+        #[test]
+        fn import_require() {
+            let (rest, import) = import_statement("import fs = require('fs');\n").unwrap();
+
+            assert_eq!(rest, "\n");
+            assert_eq!(import.identifiers.as_deref(), Some("fs"));
+            assert_eq!(import.module, "fs");
+            assert_eq!(import.kind, ImportKind::Require { export: false });
+
+            let (_, import) = import_statement("export import type x = require( \"x\" )").unwrap();
+
+            assert_eq!(import.identifiers.as_deref(), Some("x"));
+            assert_eq!(import.kind, ImportKind::Require { export: true });
+            assert_eq!(import.type_only, Some(TypeOnly::Type));
+        }
+
+        #[test]
+        fn import_type() {
+            let (_, import) = import_statement("import type { Foo } from './foo';").unwrap();
+
+            assert_eq!(import.identifiers.as_deref(), Some("{ Foo }"));
+            assert_eq!(import.type_only, Some(TypeOnly::Type));
+
+            let (_, import) = import_statement("import typeof Foo from './foo';").unwrap();
+
+            assert_eq!(import.identifiers.as_deref(), Some("Foo"));
+            assert_eq!(import.type_only, Some(TypeOnly::Typeof));
+
+            let (_, import) = import_statement("import typeof * as ns from './foo';").unwrap();
+
+            assert_eq!(import.identifiers.as_deref(), Some("* as ns"));
+            assert_eq!(import.type_only, Some(TypeOnly::Typeof));
+        }
+
+        #[test]
+        fn import_named_type() {
+            let (_, import) = import_statement("import type from './type';").unwrap();
+
+            assert_eq!(import.identifiers.as_deref(), Some("type"));
+            assert_eq!(import.type_only, None);
+
+            let (_, import) = import_statement("import typeof, { a } from './typeof';").unwrap();
+
+            assert_eq!(import.identifiers.as_deref(), Some("typeof, { a }"));
+            assert_eq!(import.type_only, None);
+
+            let (_, import) = import_statement("import type, { type a } from './type';").unwrap();
+
+            assert_eq!(import.identifiers.as_deref(), Some("type, { type a }"));
+            assert_eq!(import.type_only, None);
+
+            let (_, import) = import_statement("import typeof from './typeof';").unwrap();
+
+            assert_eq!(import.identifiers.as_deref(), Some("typeof"));
+            assert_eq!(import.type_only, None);
+        }
+
+        #[test]
+        fn dynamic_import() {
+            assert!(import_statement("import('./lazy');").is_err());
+            assert!(import_statement("import ('./lazy');").is_err());
+            assert!(import_statement("import ('./lazy')\nimport x from 'x';").is_err());
+            assert!(import_statement("import ( './lazy' ).then((m) => m.from);").is_err());
+            assert!(import_statement("import (x) = require('x');").is_err());
+        }
+
+        #[test]
+        fn import_meta() {
+            assert!(import_statement("import.meta.env.MODE;").is_err());
+            assert!(import_statement("import .meta.url").is_err());
+            assert!(namespace_alias("import .meta = x").is_err());
+        }
+
+        #[test]
+        fn bindings_named_like_from() {
+            for (code, identifiers) in [
+                ("import { fromage } from 'cheese';", "{ fromage }"),
+                ("import fromage from 'cheese';", "fromage"),
+                ("import { from } from 'a';", "{ from }"),
+                ("import { a as from } from 'a';", "{ a as from }"),
+                ("import from from 'a';", "from"),
+                ("import $from, { b } from 'a';", "$from, { b }"),
+                ("import {a}from'a';", "{a}"),
+            ] {
+                let (rest, import) = import_statement(code).expect(code);
+
+                assert_eq!(rest, "", "{code}");
+                assert_eq!(import.identifiers.as_deref(), Some(identifiers), "{code}");
+            }
+        }
+
+        #[test]
+        fn no_from_across_statements() {
+            assert!(import_statement("import Foo = Bar.Baz;\nimport x from 'x';").is_err());
+        }
+
         #[test]
         fn import_without_semicolon() {
             let (rest, import) = import_statement("import x from 'z'").unwrap();
@@ -195,9 +549,48 @@ mod tests {
                 ImportStatement {
                     identifiers: Some("x".to_owned()),
                     module: "z".to_owned(),
-                    comment: None
+                    comment: None,
+                    kind: ImportKind::Esm,
+                    type_only: None,
                 }
             )
         }
     }
+
+    mod namespace_alias {
+        use super::*;
+
+        #[test]
+        fn alias() {
+            let (rest, alias) = namespace_alias("import Foo = Bar.Baz; // foo\n").unwrap();
+
+            assert_eq!(rest, "\n");
+            assert_eq!(
+                alias,
+                NamespaceAlias {
+                    export: false,
+                    name: "Foo".to_owned(),
+                    entity: "Bar.Baz".to_owned(),
+                    comment: Some(crate::statements::Comment {
+                        data: " foo".to_owned(),
+                        ty: crate::statements::CommentType::Single
+                    })
+                }
+            );
+        }
+
+        #[test]
+        fn exported_alias() {
+            let (_, alias) = namespace_alias("export import Foo = Bar").unwrap();
+
+            assert!(alias.export);
+            assert_eq!(alias.entity, "Bar");
+        }
+
+        #[test]
+        fn not_an_alias() {
+            assert!(namespace_alias("import fs = require('fs');").is_err());
+            assert!(namespace_alias("import Foo = Bar.baz();").is_err());
+        }
+    }
 }
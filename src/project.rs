@@ -0,0 +1,168 @@
+//! Project settings, read from the configuration files around the sorted files.
+//!
+//! They are used to classify imports, see [Project::classify].
+
+mod package;
+mod tsconfig;
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    statements::{Extensions, ImportType},
+    Error,
+};
+
+use self::{package::Packages, tsconfig::TsConfig};
+
+/// The settings of the project a file belongs to.
+#[derive(Debug, Default, Hash)]
+pub struct Project {
+    tsconfig: TsConfig,
+    packages: Packages,
+}
+
+impl Project {
+    /// Loads the settings of the project containing `file`.
+    ///
+    /// To load the settings of many files, prefer [Projects] which caches them.
+    pub fn for_file(file: &Path) -> Rc<Self> {
+        Projects::default().for_file(file)
+    }
+
+    /// Loads the settings of the project in `dir`.
+    ///
+    /// Config files which can't be read are ignored with a warning, so that they don't stop the
+    /// sorting of the files they don't belong to.
+    fn load(dir: &Path, loader: &mut Loader) -> Self {
+        let tsconfig = TsConfig::find(dir).unwrap_or_else(|err| {
+            loader.warn(err);
+            TsConfig::default()
+        });
+        let packages = Packages::find(dir, loader).unwrap_or_else(|err| {
+            loader.warn(err);
+            Packages::default()
+        });
+
+        Self { tsconfig, packages }
+    }
+
+    /// Classifies an import by its module.
+    ///
+    /// In order of priority:
+    ///
+    /// - packages of the workspace are [Workspace](ImportType::Workspace) imports
+    /// - declared dependencies are never aliases
+    /// - modules matching the `tsconfig.json` / `jsconfig.json` paths are aliases
+    pub fn classify(&self, module: &str, extensions: &Extensions) -> ImportType {
+        if let Some(ty) = self.packages.classify(module, extensions) {
+            ty
+        } else if self.tsconfig.is_alias(module) {
+            ImportType::Alias
+        } else {
+            ImportType::classify(module, extensions)
+        }
+    }
+
+    /// Tells whether `module` resolves through the `baseUrl`, if the files of the project are
+    /// looked for to classify it.
+    ///
+    /// Returns [None] if the classification of `module` only depends on the config files.
+    pub(crate) fn probe(&self, module: &str) -> Option<bool> {
+        let known = self
+            .packages
+            .classify(module, &Extensions::default())
+            .is_some();
+
+        (!known && self.tsconfig.probes(module)).then(|| self.tsconfig.is_alias(module))
+    }
+
+    /// Returns the absolute directory of a file.
+    fn dir(file: &Path) -> PathBuf {
+        let dir = match file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        fs::canonicalize(dir).unwrap_or_else(|_| dir.to_owned())
+    }
+}
+
+/// Loads projects, caching them by directory.
+#[derive(Debug, Default)]
+pub struct Projects {
+    projects: HashMap<PathBuf, Rc<Project>>,
+    loader: Loader,
+}
+
+impl Projects {
+    /// Returns the settings of the project containing `file`.
+    pub fn for_file(&mut self, file: &Path) -> Rc<Project> {
+        let dir = Project::dir(file);
+
+        if let Some(project) = self.projects.get(&dir) {
+            return project.clone();
+        }
+
+        let project = Rc::new(Project::load(&dir, &mut self.loader));
+        self.projects.insert(dir, project.clone());
+
+        project
+    }
+
+    /// Forgets the loaded projects, to read their config files again.
+    #[cfg(feature = "lsp")]
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// What is shared by the loads of many projects.
+#[derive(Debug, Default)]
+pub(crate) struct Loader {
+    /// Workspace packages by workspace root.
+    pub(crate) workspaces: HashMap<PathBuf, Rc<BTreeSet<String>>>,
+    /// Warnings already printed.
+    pub(crate) warnings: HashSet<String>,
+}
+
+impl Loader {
+    /// Prints a warning about a config file which is ignored, unless it was already printed.
+    pub(crate) fn warn(&mut self, err: Error) {
+        let message = match err {
+            Error::Jsort { err } => err,
+            err => err.to_string(),
+        };
+
+        if self.warnings.insert(message.clone()) {
+            eprintln!("\x1b[33mjisort warning:\x1b[m {}, ignored", message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_tsconfig() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("tsconfig.json"), "{ \"compilerOptions\": ").unwrap();
+        fs::write(dir.path().join("package.json"), r#"{ "name": "app" }"#).unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+
+        let mut projects = Projects::default();
+        let project = projects.for_file(&dir.path().join("index.ts"));
+
+        assert_eq!(
+            project.classify("app", &Default::default()),
+            ImportType::Workspace
+        );
+        projects.for_file(&dir.path().join("src/index.ts"));
+        assert_eq!(projects.loader.warnings.len(), 1);
+    }
+}
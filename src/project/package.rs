@@ -0,0 +1,373 @@
+//! Reads dependencies & workspace packages out of `package.json` files.
+
+use std::{collections::BTreeSet, fs, path::Path, rc::Rc};
+
+use ignore::{overrides::OverrideBuilder, WalkBuilder};
+use serde_json::Value;
+
+use crate::{
+    statements::{Extensions, ImportType},
+    Error,
+};
+
+use super::Loader;
+
+/// Dependency lists of a `package.json`.
+const DEPENDENCIES: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// The packages known by the `package.json` files around a file.
+#[derive(Debug, Default, Clone, Hash)]
+pub struct Packages {
+    /// Declared third-party dependencies.
+    external: BTreeSet<String>,
+    /// Packages of the workspace, including the current one.
+    internal: Rc<BTreeSet<String>>,
+}
+
+impl Packages {
+    /// Reads the nearest `package.json` from `dir`, and the workspace it belongs to.
+    ///
+    /// Workspaces are cached by root directory in the `loader`.
+    /// Manifests which can't be read are skipped with a warning.
+    pub(crate) fn find(dir: &Path, loader: &mut Loader) -> Result<Self, Error> {
+        let mut packages = Self::default();
+        let mut internal = BTreeSet::new();
+        let mut nearest = true;
+
+        for dir in dir.ancestors() {
+            let manifest = dir.join("package.json");
+            let json = if manifest.is_file() {
+                read_json(&manifest).map_err(|err| loader.warn(err)).ok()
+            } else {
+                None
+            };
+
+            if let Some(json) = &json {
+                if nearest {
+                    internal.extend(json["name"].as_str().map(String::from));
+                    nearest = false;
+                }
+
+                for (name, version) in DEPENDENCIES
+                    .iter()
+                    .filter_map(|list| json[list].as_object())
+                    .flatten()
+                {
+                    // `workspace:` protocol of yarn & pnpm
+                    if version
+                        .as_str()
+                        .is_some_and(|v| v.starts_with("workspace:"))
+                    {
+                        internal.insert(name.clone());
+                    } else {
+                        packages.external.insert(name.clone());
+                    }
+                }
+            }
+
+            let patterns = workspace_patterns(dir, json.as_ref())?;
+            if !patterns.is_empty() {
+                let workspace = match loader.workspaces.get(dir) {
+                    Some(workspace) => workspace.clone(),
+                    None => {
+                        let workspace = Rc::new(workspace_packages(dir, &patterns, loader)?);
+                        loader.workspaces.insert(dir.to_owned(), workspace.clone());
+                        workspace
+                    }
+                };
+                internal.extend(workspace.iter().cloned());
+                break;
+            }
+        }
+
+        for name in &internal {
+            packages.external.remove(name);
+        }
+        packages.internal = Rc::new(internal);
+
+        Ok(packages)
+    }
+
+    /// Classifies a module by its package.
+    ///
+    /// Returns [None] if the package is unknown.
+    pub fn classify(&self, module: &str, extensions: &Extensions) -> Option<ImportType> {
+        let package = package_name(module)?;
+
+        if self.internal.contains(package) {
+            Some(ImportType::Workspace)
+        } else if self.external.contains(package) {
+            Some(ImportType::classify(module, extensions))
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the package name of a module, like `lodash` for `lodash/fp` or `@acme/ui` for
+/// `@acme/ui/button`.
+fn package_name(module: &str) -> Option<&str> {
+    if module.starts_with('.') || module.starts_with('/') {
+        return None;
+    }
+
+    let end = if module.starts_with('@') {
+        module.match_indices('/').nth(1).map(|(i, _)| i)
+    } else {
+        module.find('/')
+    };
+
+    Some(&module[..end.unwrap_or(module.len())])
+}
+
+/// Returns the workspace patterns declared in `dir`, either in the `package.json` `workspaces`
+/// (npm & yarn) or in `pnpm-workspace.yaml`.
+fn workspace_patterns(dir: &Path, package: Option<&Value>) -> Result<Vec<String>, Error> {
+    if let Some(package) = package {
+        let workspaces = match &package["workspaces"] {
+            Value::Array(patterns) => Some(patterns),
+            Value::Object(workspaces) => workspaces.get("packages").and_then(Value::as_array),
+            _ => None,
+        };
+
+        if let Some(workspaces) = workspaces {
+            return Ok(workspaces
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect());
+        }
+    }
+
+    let pnpm = dir.join("pnpm-workspace.yaml");
+    if pnpm.is_file() {
+        return Ok(pnpm_packages(&fs::read_to_string(pnpm)?));
+    }
+
+    Ok(vec![])
+}
+
+/// Reads the `packages` list of a `pnpm-workspace.yaml`.
+fn pnpm_packages(content: &str) -> Vec<String> {
+    let mut packages = vec![];
+    let mut in_packages = false;
+
+    for line in content.lines() {
+        let line = line.split(" #").next().unwrap_or_default().trim_end();
+
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = line.starts_with("packages:");
+        } else if in_packages {
+            if let Some(item) = line.trim_start().strip_prefix('-') {
+                packages.push(item.trim().trim_matches(['\'', '"']).to_owned());
+            }
+        }
+    }
+
+    packages
+}
+
+/// Returns the names of the workspace packages matching `patterns`.
+fn workspace_packages(
+    root: &Path,
+    patterns: &[String],
+    loader: &mut Loader,
+) -> Result<BTreeSet<String>, Error> {
+    let mut globs = OverrideBuilder::new(root);
+    for pattern in patterns {
+        let (negation, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => ("!", pattern),
+            None => ("", pattern.as_str()),
+        };
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        globs.add(&format!("{}/{}/package.json", negation, pattern))?;
+    }
+
+    let mut names = BTreeSet::new();
+    for entry in WalkBuilder::new(root)
+        .overrides(globs.build()?)
+        // Installed packages are never part of the workspace, nor walked
+        .filter_entry(|e| e.file_name() != "node_modules")
+        .build()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name() == "package.json")
+    {
+        match read_json(entry.path()) {
+            Ok(json) => names.extend(json["name"].as_str().map(String::from)),
+            Err(err) => loader.warn(err),
+        }
+    }
+
+    Ok(names)
+}
+
+/// Reads a JSON file, errors mention its path.
+fn read_json(path: &Path) -> Result<Value, Error> {
+    let content = fs::read_to_string(path).map_err(|e| Error::Jsort {
+        err: format!("{}: {}", path.display(), e),
+    })?;
+
+    serde_json::from_str(&content).map_err(|e| Error::Jsort {
+        err: format!("{}: {}", path.display(), e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn package_names() {
+        assert_eq!(package_name("lodash"), Some("lodash"));
+        assert_eq!(package_name("lodash/fp"), Some("lodash"));
+        assert_eq!(package_name("@acme/ui"), Some("@acme/ui"));
+        assert_eq!(package_name("@acme/ui/button"), Some("@acme/ui"));
+        assert_eq!(package_name("./local"), None);
+    }
+
+    #[test]
+    fn pnpm_workspace() {
+        let content = "packages:\n  # apps\n  - 'apps/*'\n  - \"packages/**\" # all\n  - '!**/test/**'\ncatalog:\n  - foo\n";
+
+        assert_eq!(
+            pnpm_packages(content),
+            vec!["apps/*", "packages/**", "!**/test/**"]
+        );
+    }
+
+    #[test]
+    fn npm_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{ "workspaces": ["packages/*"], "devDependencies": { "jest": "^29" } }"#,
+        );
+        write(
+            root,
+            "packages/ui/package.json",
+            r#"{ "name": "@acme/ui", "dependencies": { "@emotion/react": "^11" } }"#,
+        );
+        write(
+            root,
+            "packages/app/package.json",
+            r#"{ "name": "@acme/app", "dependencies": { "@acme/ui": "*", "react": "^18" } }"#,
+        );
+
+        let packages =
+            Packages::find(&root.join("packages/app/src"), &mut Loader::default()).unwrap();
+
+        assert_eq!(
+            packages.classify("@acme/ui/button", &Default::default()),
+            Some(ImportType::Workspace)
+        );
+        assert_eq!(
+            packages.classify("@acme/app", &Default::default()),
+            Some(ImportType::Workspace)
+        );
+        assert_eq!(
+            packages.classify("react", &Default::default()),
+            Some(ImportType::Global)
+        );
+        assert_eq!(
+            packages.classify("jest", &Default::default()),
+            Some(ImportType::Module)
+        );
+        assert_eq!(
+            packages.classify("@emotion/react", &Default::default()),
+            None
+        );
+        assert_eq!(packages.classify("./local", &Default::default()), None);
+    }
+
+    #[test]
+    fn workspace_protocol() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "package.json",
+            r#"{ "name": "app", "dependencies": { "@acme/ui": "workspace:*" } }"#,
+        );
+
+        let packages = Packages::find(dir.path(), &mut Loader::default()).unwrap();
+
+        assert_eq!(
+            packages.classify("@acme/ui", &Default::default()),
+            Some(ImportType::Workspace)
+        );
+    }
+
+    #[test]
+    fn malformed_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{ "workspaces": ["packages/*"], "dependencies": { "react": "^18" } }"#,
+        );
+        write(
+            root,
+            "packages/ui/package.json",
+            r#"{ "name": "@acme/ui" }"#,
+        );
+        write(root, "packages/broken/package.json", "{ \"name\": ");
+        write(root, "packages/app/package.json", "not json");
+
+        let mut loader = Loader::default();
+        let packages = Packages::find(&root.join("packages/app"), &mut loader).unwrap();
+
+        assert_eq!(
+            packages.classify("@acme/ui", &Default::default()),
+            Some(ImportType::Workspace)
+        );
+        assert_eq!(
+            packages.classify("react", &Default::default()),
+            Some(ImportType::Global)
+        );
+        assert_eq!(loader.warnings.len(), 2);
+    }
+
+    #[test]
+    fn pnpm_workspace_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "package.json", r#"{ "name": "root" }"#);
+        write(root, "pnpm-workspace.yaml", "packages:\n  - 'libs/*'\n");
+        write(
+            root,
+            "libs/utils/package.json",
+            r#"{ "name": "@acme/utils" }"#,
+        );
+        write(root, "app/package.json", r#"{ "name": "app" }"#);
+        write(
+            root,
+            "libs/node_modules/package.json",
+            r#"{ "name": "installed" }"#,
+        );
+
+        let packages = Packages::find(&root.join("app"), &mut Loader::default()).unwrap();
+
+        assert_eq!(
+            packages.classify("@acme/utils", &Default::default()),
+            Some(ImportType::Workspace)
+        );
+        assert_eq!(packages.classify("installed", &Default::default()), None);
+    }
+}
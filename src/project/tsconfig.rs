@@ -0,0 +1,306 @@
+//! Reads path aliases out of `tsconfig.json` / `jsconfig.json` files.
+
+use std::{
+    fs,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::Chars,
+};
+
+use serde_json::Value;
+
+use crate::Error;
+
+/// Names of the config files, by priority.
+const FILE_NAMES: [&str; 2] = ["tsconfig.json", "jsconfig.json"];
+/// Extensions tried when resolving a module through `baseUrl`.
+const EXTENSIONS: [&str; 8] = ["", ".ts", ".tsx", ".d.ts", ".js", ".jsx", ".mjs", ".cjs"];
+
+/// The path aliases of a TypeScript or JavaScript project.
+#[derive(Debug, Default, Clone, Hash)]
+pub struct TsConfig {
+    /// Patterns of `compilerOptions.paths`, like `~/*` or `@app/*`.
+    paths: Vec<String>,
+    /// Absolute `compilerOptions.baseUrl`.
+    base_url: Option<PathBuf>,
+}
+
+impl TsConfig {
+    /// Finds the nearest config file from `dir` and reads it, following `extends`.
+    ///
+    /// Returns an empty config if there is none.
+    pub fn find(dir: &Path) -> Result<Self, Error> {
+        let file = dir.ancestors().find_map(|dir| {
+            FILE_NAMES
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+        });
+
+        match file {
+            Some(file) => Self::load(&file, 0),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Reads a config file, following `extends`.
+    fn load(file: &Path, depth: usize) -> Result<Self, Error> {
+        if depth > 32 {
+            return Err(Error::Jsort {
+                err: format!("{}: too many nested `extends`", file.display()),
+            });
+        }
+
+        let content = fs::read_to_string(file).map_err(|e| Error::Jsort {
+            err: format!("{}: {}", file.display(), e),
+        })?;
+        let json: Value =
+            serde_json::from_str(&strip_jsonc(&content)).map_err(|e| Error::Jsort {
+                err: format!("{}: {}", file.display(), e),
+            })?;
+        let dir = file.parent().unwrap_or(Path::new("."));
+
+        // Later entries of `extends` override earlier ones
+        let mut config = Self::default();
+        let extends = match &json["extends"] {
+            Value::String(extends) => vec![extends.as_str()],
+            Value::Array(extends) => extends.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        for extends in extends {
+            if let Some(parent) = resolve_extends(dir, extends) {
+                config.merge(Self::load(&parent, depth + 1)?);
+            }
+        }
+
+        let options = &json["compilerOptions"];
+        if let Some(base_url) = options["baseUrl"].as_str() {
+            config.base_url = Some(dir.join(base_url));
+        }
+        if let Some(paths) = options["paths"].as_object() {
+            config.paths = paths.keys().cloned().collect();
+        }
+
+        Ok(config)
+    }
+
+    /// Overrides this config with `other`'s options.
+    fn merge(&mut self, other: Self) {
+        if other.base_url.is_some() {
+            self.base_url = other.base_url;
+        }
+        if !other.paths.is_empty() {
+            self.paths = other.paths;
+        }
+    }
+
+    /// Tells whether a module is an alias, matching `paths` or resolvable through `baseUrl`.
+    pub fn is_alias(&self, module: &str) -> bool {
+        if module.starts_with('.') || module.starts_with('/') {
+            return false;
+        }
+
+        self.paths.iter().any(|pattern| matches(pattern, module))
+            || self.base_url.as_ref().is_some_and(|base_url| {
+                let path = base_url.join(module);
+                EXTENSIONS.iter().any(|ext| {
+                    let mut file = path.clone().into_os_string();
+                    file.push(ext);
+                    Path::new(&file).is_file()
+                }) || path.is_dir()
+            })
+    }
+
+    /// Tells whether [TsConfig::is_alias] looks for `module` on the filesystem, through `baseUrl`.
+    pub fn probes(&self, module: &str) -> bool {
+        self.base_url.is_some()
+            && !module.starts_with('.')
+            && !module.starts_with('/')
+            && !self.paths.iter().any(|pattern| matches(pattern, module))
+    }
+}
+
+/// Matches a `paths` pattern, which can contain a single `*` wildcard.
+fn matches(pattern: &str, module: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            module.len() >= prefix.len() + suffix.len()
+                && module.starts_with(prefix)
+                && module.ends_with(suffix)
+        }
+        None => pattern == module,
+    }
+}
+
+/// Resolves the path of an `extends` entry, relative path or package.
+fn resolve_extends(dir: &Path, extends: &str) -> Option<PathBuf> {
+    let with_json = |path: PathBuf| {
+        if path.is_file() {
+            return Some(path);
+        }
+        let mut json = path.clone().into_os_string();
+        json.push(".json");
+        let json = PathBuf::from(json);
+        if json.is_file() {
+            return Some(json);
+        }
+        let index = path.join("tsconfig.json");
+        index.is_file().then_some(index)
+    };
+
+    if extends.starts_with('.') || Path::new(extends).is_absolute() {
+        return with_json(dir.join(extends));
+    }
+
+    dir.ancestors()
+        .find_map(|dir| with_json(dir.join("node_modules").join(extends)))
+}
+
+/// Removes comments & trailing commas, which are allowed in tsconfig files but not in JSON.
+fn strip_jsonc(content: &str) -> String {
+    let mut res = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            res.push(c);
+            match c {
+                '\\' => res.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                res.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            (',', _) => {
+                // Drop the comma if the next significant character closes an object or array
+                if !matches!(next_significant(chars.clone()), Some('}' | ']')) {
+                    res.push(c);
+                }
+            }
+            _ => res.push(c),
+        }
+    }
+
+    res
+}
+
+/// Returns the next character which is neither a whitespace nor part of a comment.
+fn next_significant(mut chars: Peekable<Chars>) -> Option<char> {
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            (c, _) if c.is_whitespace() => {}
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            _ => return Some(c),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jsonc() {
+        let content = r#"{
+            // comment
+            "a": "b // not a comment", /* comment */
+            "c": [1, 2, /* comment */ ],
+        }"#;
+
+        let json: Value = serde_json::from_str(&strip_jsonc(content)).unwrap();
+
+        assert_eq!(json["a"], "b // not a comment");
+        assert_eq!(json["c"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn pattern() {
+        assert!(matches("~/*", "~/components"));
+        assert!(matches("#components/*", "#components/Button"));
+        assert!(!matches("#components/*", "#utils/phone"));
+        assert!(matches("config", "config"));
+        assert!(!matches("config", "config/foo"));
+    }
+
+    #[test]
+    fn paths_with_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("tsconfig.base.json"),
+            r#"{ "compilerOptions": { "paths": { "~/*": ["src/*"], "@app/*": ["app/*"] } } }"#,
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("app")).unwrap();
+        fs::write(
+            dir.path().join("app/tsconfig.json"),
+            r#"{
+                // Options are inherited
+                "extends": "../tsconfig.base",
+            }"#,
+        )
+        .unwrap();
+
+        let config = TsConfig::find(&dir.path().join("app")).unwrap();
+
+        assert!(config.is_alias("~/utils"));
+        assert!(config.is_alias("@app/components"));
+        assert!(!config.is_alias("@testing-library/react"));
+        assert!(!config.is_alias("./local"));
+    }
+
+    #[test]
+    fn base_url() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("jsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": "src" } }"#,
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join("src/components")).unwrap();
+        fs::write(dir.path().join("src/utils.js"), "").unwrap();
+
+        let config = TsConfig::find(dir.path()).unwrap();
+
+        assert!(config.is_alias("components"));
+        assert!(config.is_alias("utils"));
+        assert!(!config.is_alias("lodash"));
+    }
+}
@@ -0,0 +1,550 @@
+//! Sorting of imports, see [Sorter].
+
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use crate::{
+    cache::StableHasher,
+    project::Project,
+    statements::{
+        Comment, CommentType, Extensions, ImportStatement, ImportType, Statement, BUILTIN_MODULES,
+    },
+    Config, ModuleOrder, NodePrefix, RelativeOrder,
+};
+
+/// Sorts imports following the [Config] options and the settings of their [Project].
+#[derive(Debug, Clone)]
+pub struct Sorter {
+    project: Rc<Project>,
+    extensions: Rc<Extensions>,
+    node_prefix: NodePrefix,
+    module_order: ModuleOrder,
+    relative_order: RelativeOrder,
+    group_blank_lines: usize,
+    group_headers: bool,
+    /// Texts of the group headers, replacing the [names](ImportType::name) of their groups.
+    group_header_texts: Rc<BTreeMap<ImportType, String>>,
+    hoist: bool,
+}
+
+impl Default for Sorter {
+    fn default() -> Self {
+        Self::new(&Config::default(), Default::default())
+    }
+}
+
+impl Sorter {
+    /// Creates a sorter for the files of a project.
+    pub fn new(config: &Config, project: Rc<Project>) -> Self {
+        Self {
+            project,
+            extensions: Rc::new(config.extensions()),
+            node_prefix: config.node_prefix,
+            module_order: config.module_order,
+            relative_order: config.relative_order,
+            group_blank_lines: config.group_blank_lines,
+            group_headers: config.group_headers,
+            group_header_texts: Rc::new(config.group_header_texts()),
+            hoist: config.hoist,
+        }
+    }
+
+    /// Returns the type of an import, which is its group.
+    pub fn ty(&self, import: &ImportStatement) -> ImportType {
+        self.project.classify(&import.module, &self.extensions)
+    }
+
+    /// Tells whether a module resolves through the `baseUrl`, if the files of the project are
+    /// looked for to classify it, see [Project::probe].
+    pub(crate) fn probe(&self, module: &str) -> Option<bool> {
+        self.project.probe(module)
+    }
+
+    /// Returns the module of an import, as it should be written.
+    ///
+    /// This adds or removes the `node:` prefix of built-in modules, see [NodePrefix].
+    pub fn module<'a>(&self, import: &'a ImportStatement) -> Cow<'a, str> {
+        let module = import.module.as_str();
+
+        match self.node_prefix {
+            NodePrefix::Add if BUILTIN_MODULES.contains(&module) => {
+                Cow::Owned(format!("node:{}", module))
+            }
+            NodePrefix::Remove => match module.strip_prefix("node:") {
+                Some(bare) if BUILTIN_MODULES.contains(&bare) => Cow::Borrowed(bare),
+                _ => Cow::Borrowed(module),
+            },
+            _ => Cow::Borrowed(module),
+        }
+    }
+
+    /// Returns an import as it should be written.
+    pub fn normalize(&self, import: &ImportStatement) -> ImportStatement {
+        ImportStatement {
+            module: self.module(import).into_owned(),
+            ..import.clone()
+        }
+    }
+
+    /// Returns the indices of `imports` in sorted order.
+    ///
+    /// The sort is stable.
+    pub fn order(&self, imports: &[ImportStatement]) -> Vec<usize> {
+        let keys: Vec<_> = imports
+            .iter()
+            .map(|import| (self.ty(import), self.module(import)))
+            .collect();
+        let mut order: Vec<_> = (0..imports.len()).collect();
+
+        order.sort_by(|a, b| {
+            let (a, b) = (&keys[*a], &keys[*b]);
+            a.0.cmp(&b.0)
+                .then_with(|| self.cmp_relative(&a.1, &b.1))
+                .then_with(|| self.cmp_modules(&a.1, &b.1))
+        });
+
+        order
+    }
+
+    /// Sorts imports, and normalizes them.
+    pub fn sort(&self, imports: &[ImportStatement]) -> Vec<ImportStatement> {
+        self.order(imports)
+            .into_iter()
+            .map(|i| self.normalize(&imports[i]))
+            .collect()
+    }
+
+    /// Tells whether imports are sorted, and written as they should be.
+    pub fn is_sorted(&self, imports: &[ImportStatement]) -> bool {
+        self.order(imports)
+            .into_iter()
+            .enumerate()
+            .all(|(i, j)| i == j)
+            && imports
+                .iter()
+                .all(|import| self.module(import) == import.module)
+    }
+
+    /// Compares two modules of the same type, following the [ModuleOrder].
+    ///
+    /// Modules equal ignoring case are compared byte-wise, so the order is always total.
+    fn cmp_modules(&self, a: &str, b: &str) -> Ordering {
+        match self.module_order {
+            ModuleOrder::Bytewise => a.cmp(b),
+            ModuleOrder::CaseInsensitive => cmp_case_insensitive(a, b).then_with(|| a.cmp(b)),
+            ModuleOrder::Natural => cmp_natural(a, b).then_with(|| a.cmp(b)),
+            ModuleOrder::Eslint => cmp_eslint(a, b),
+        }
+    }
+
+    /// Number of blank lines between import groups.
+    pub fn group_blank_lines(&self) -> usize {
+        self.group_blank_lines
+    }
+
+    /// Whether scattered imports are moved up into the sorted imports, when it is safe.
+    pub fn hoist(&self) -> bool {
+        self.hoist
+    }
+
+    /// Returns the header comment written before the group of an import type, if enabled.
+    ///
+    /// Its text is the one given in the [Config], or the name of the group.
+    pub fn header(&self, ty: ImportType) -> Option<Comment> {
+        let text = self
+            .group_header_texts
+            .get(&ty)
+            .map_or(ty.name(), String::as_str);
+
+        self.group_headers.then(|| Comment {
+            data: format!(" {}", text),
+            ty: CommentType::Single,
+        })
+    }
+
+    /// Tells whether a statement is a generated group header, which is regenerated when
+    /// rendering.
+    pub fn is_header(&self, statement: &dyn Statement) -> bool {
+        self.group_headers
+            && ImportType::ALL.iter().any(|ty| {
+                self.header(*ty)
+                    .is_some_and(|header| header.code() == statement.code())
+            })
+    }
+
+    /// Compares two relative modules by their distance, following the [RelativeOrder].
+    ///
+    /// Returns [Ordering::Equal] if one of the modules is not relative.
+    fn cmp_relative(&self, a: &str, b: &str) -> Ordering {
+        let (Some(a), Some(b)) = (distance(a), distance(b)) else {
+            return Ordering::Equal;
+        };
+
+        match self.relative_order {
+            RelativeOrder::Lexical => Ordering::Equal,
+            RelativeOrder::FarthestFirst => b.cmp(&a),
+            RelativeOrder::ClosestFirst => a.cmp(&b),
+        }
+    }
+
+    /// Hash of the settings, which changes whenever the result of a sort could.
+    ///
+    /// The files looked for to classify imports are not part of it, see [Sorter::probe].
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        self.project.hash(&mut hasher);
+        self.extensions.hash(&mut hasher);
+        self.node_prefix.hash(&mut hasher);
+        self.module_order.hash(&mut hasher);
+        self.relative_order.hash(&mut hasher);
+        self.group_blank_lines.hash(&mut hasher);
+        self.group_headers.hash(&mut hasher);
+        self.group_header_texts.hash(&mut hasher);
+        self.hoist.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Distance of a relative module from the importing file: `0` for the index (`.` or
+/// `./index`), `1` for siblings, and `1 + n` for the `n`th parent directory.
+///
+/// Returns [None] if the module is not relative.
+fn distance(module: &str) -> Option<usize> {
+    if module != "." && module != ".." && !module.starts_with("./") && !module.starts_with("../") {
+        return None;
+    }
+
+    let mut segments = module
+        .split('/')
+        .filter(|segment| *segment != ".")
+        .peekable();
+    let mut parents = 0;
+
+    while segments.next_if_eq(&"..").is_some() {
+        parents += 1;
+    }
+
+    let rest: Vec<_> = segments.filter(|segment| !segment.is_empty()).collect();
+    let is_index = match rest.as_slice() {
+        [] => true,
+        [name] => *name == "index" || name.starts_with("index."),
+        _ => false,
+    };
+
+    Some(match (parents, is_index) {
+        (0, true) => 0,
+        (parents, _) => 1 + parents,
+    })
+}
+
+fn cmp_case_insensitive(a: &str, b: &str) -> Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Compares strings ignoring case, with runs of digits compared by their value.
+fn cmp_natural(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+
+    loop {
+        let (a_chunk, a_rest) = split_chunk(a);
+        let (b_chunk, b_rest) = split_chunk(b);
+
+        let ordering = match (a_chunk, b_chunk) {
+            ("", "") => return Ordering::Equal,
+            (a_chunk, b_chunk) if is_number(a_chunk) && is_number(b_chunk) => {
+                let a_digits = a_chunk.trim_start_matches('0');
+                let b_digits = b_chunk.trim_start_matches('0');
+
+                a_digits
+                    .len()
+                    .cmp(&b_digits.len())
+                    .then_with(|| a_digits.cmp(b_digits))
+                    .then_with(|| a_chunk.len().cmp(&b_chunk.len()))
+            }
+            (a_chunk, b_chunk) => cmp_case_insensitive(a_chunk, b_chunk),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+
+        (a, b) = (a_rest, b_rest);
+    }
+}
+
+/// Splits the leading run of digits, or of non-digits, of a string.
+fn split_chunk(value: &str) -> (&str, &str) {
+    let digits = value.starts_with(|c: char| c.is_ascii_digit());
+    let end = value
+        .find(|c: char| c.is_ascii_digit() != digits)
+        .unwrap_or(value.len());
+
+    value.split_at(end)
+}
+
+fn is_number(chunk: &str) -> bool {
+    chunk.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Compares strings as eslint-plugin-import: segment by segment, split on `/`, and by UTF-16
+/// code units like JavaScript strings.
+fn cmp_eslint(a: &str, b: &str) -> Ordering {
+    let utf16 = |a: &str, b: &str| a.encode_utf16().cmp(b.encode_utf16());
+
+    if !a.contains('/') && !b.contains('/') {
+        return utf16(a, b);
+    }
+
+    let (a, b): (Vec<_>, Vec<_>) = (a.split('/').collect(), b.split('/').collect());
+
+    a.iter()
+        .zip(&b)
+        .map(|(a, b)| utf16(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statements::ImportKind;
+
+    fn import(module: &str) -> ImportStatement {
+        ImportStatement {
+            identifiers: None,
+            module: module.to_owned(),
+            comment: None,
+            kind: ImportKind::Esm,
+            type_only: None,
+        }
+    }
+
+    fn sorter(node_prefix: NodePrefix) -> Sorter {
+        Sorter::new(
+            &Config {
+                node_prefix,
+                ..Default::default()
+            },
+            Default::default(),
+        )
+    }
+
+    fn sorted(module_order: ModuleOrder, modules: &[&str]) -> Vec<String> {
+        let sorter = Sorter::new(
+            &Config {
+                module_order,
+                ..Default::default()
+            },
+            Default::default(),
+        );
+        let imports: Vec<_> = modules.iter().map(|module| import(module)).collect();
+
+        sorter
+            .sort(&imports)
+            .into_iter()
+            .map(|import| import.module)
+            .collect()
+    }
+
+    #[test]
+    fn bytewise_order() {
+        assert_eq!(
+            sorted(ModuleOrder::Bytewise, &["axios", "Zod", "file2", "file10"]),
+            vec!["Zod", "axios", "file10", "file2"]
+        );
+    }
+
+    #[test]
+    fn case_insensitive_order() {
+        assert_eq!(
+            sorted(
+                ModuleOrder::CaseInsensitive,
+                &["Zod", "axios", "zod", "Axios"]
+            ),
+            vec!["Axios", "axios", "Zod", "zod"]
+        );
+    }
+
+    #[test]
+    fn natural_order() {
+        assert_eq!(
+            sorted(
+                ModuleOrder::Natural,
+                &["./file10", "./File2", "./file1", "./file02", "./file"]
+            ),
+            vec!["./file", "./file1", "./File2", "./file02", "./file10"]
+        );
+    }
+
+    #[test]
+    fn eslint_order() {
+        assert_eq!(
+            sorted(
+                ModuleOrder::Eslint,
+                &["./b", "../a", "./a/b", "./a", "./a-b", "Zod", "axios"]
+            ),
+            vec!["Zod", "axios", "./a", "./a/b", "./a-b", "./b", "../a"]
+        );
+    }
+
+    fn sorted_relative(relative_order: RelativeOrder, modules: &[&str]) -> Vec<String> {
+        let sorter = Sorter::new(
+            &Config {
+                relative_order,
+                ..Default::default()
+            },
+            Default::default(),
+        );
+        let imports: Vec<_> = modules.iter().map(|module| import(module)).collect();
+
+        sorter
+            .sort(&imports)
+            .into_iter()
+            .map(|import| import.module)
+            .collect()
+    }
+
+    const RELATIVE: [&str; 6] = ["./index", "./Button", "../utils", ".", "../../api", "./a"];
+
+    #[test]
+    fn lexical_relative_order() {
+        assert_eq!(
+            sorted_relative(RelativeOrder::Lexical, &RELATIVE),
+            vec![".", "../../api", "../utils", "./Button", "./a", "./index"]
+        );
+    }
+
+    #[test]
+    fn farthest_first() {
+        assert_eq!(
+            sorted_relative(RelativeOrder::FarthestFirst, &RELATIVE),
+            vec!["../../api", "../utils", "./Button", "./a", ".", "./index"]
+        );
+    }
+
+    #[test]
+    fn closest_first() {
+        assert_eq!(
+            sorted_relative(RelativeOrder::ClosestFirst, &RELATIVE),
+            vec![".", "./index", "./Button", "./a", "../utils", "../../api"]
+        );
+    }
+
+    #[test]
+    fn relative_distance() {
+        assert_eq!(distance("."), Some(0));
+        assert_eq!(distance("./"), Some(0));
+        assert_eq!(distance("./index.js"), Some(0));
+        assert_eq!(distance("./indexes"), Some(1));
+        assert_eq!(distance("./components/index"), Some(1));
+        assert_eq!(distance(".."), Some(2));
+        assert_eq!(distance("../index"), Some(2));
+        assert_eq!(distance("../../utils"), Some(3));
+        assert_eq!(distance(".eslintrc"), None);
+        assert_eq!(distance("react"), None);
+    }
+
+    #[test]
+    fn builtin_first() {
+        let imports = [import("react"), import("./local"), import("fs")];
+
+        assert_eq!(sorter(NodePrefix::Keep).order(&imports), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn styles_and_assets_last() {
+        let imports = [
+            import("./logo.svg"),
+            import("./global.scss"),
+            import("./button.module.scss"),
+            import("./button"),
+        ];
+
+        assert_eq!(sorter(NodePrefix::Keep).order(&imports), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn keep_node_prefix() {
+        let sorter = sorter(NodePrefix::Keep);
+
+        assert_eq!(sorter.module(&import("fs")), "fs");
+        assert_eq!(sorter.module(&import("node:fs")), "node:fs");
+    }
+
+    #[test]
+    fn add_node_prefix() {
+        let sorter = sorter(NodePrefix::Add);
+
+        assert_eq!(sorter.module(&import("fs")), "node:fs");
+        assert_eq!(sorter.module(&import("fs/promises")), "node:fs/promises");
+        assert_eq!(sorter.module(&import("node:fs")), "node:fs");
+        assert_eq!(sorter.module(&import("fs-extra")), "fs-extra");
+        assert!(!sorter.is_sorted(&[import("fs")]));
+        assert!(sorter.is_sorted(&[import("node:fs")]));
+    }
+
+    #[test]
+    fn remove_node_prefix() {
+        let sorter = sorter(NodePrefix::Remove);
+
+        assert_eq!(sorter.module(&import("node:fs")), "fs");
+        assert_eq!(sorter.module(&import("fs")), "fs");
+        // Only exists with the prefix
+        assert_eq!(sorter.module(&import("node:test")), "node:test");
+    }
+
+    #[test]
+    fn sort_with_normalized_modules() {
+        let imports = [import("path"), import("node:fs")];
+
+        let sorted = sorter(NodePrefix::Add).sort(&imports);
+
+        assert_eq!(sorted, vec![import("node:fs"), import("node:path")]);
+    }
+
+    #[test]
+    fn group_header_texts() {
+        let config = Config {
+            group_headers: true,
+            group_header: vec![
+                "module=External".to_owned(),
+                " local = Internal ".to_owned(),
+                "unknown=Ignored".to_owned(),
+                "alias=".to_owned(),
+            ],
+            ..Default::default()
+        };
+        let sorter = Sorter::new(&config, Default::default());
+        let header = |ty| sorter.header(ty).map(|header| header.code());
+
+        assert_eq!(header(ImportType::Module).as_deref(), Some("// External"));
+        assert_eq!(header(ImportType::Local).as_deref(), Some("// Internal"));
+        assert_eq!(header(ImportType::Alias).as_deref(), Some("// Aliases"));
+        assert_ne!(
+            sorter.fingerprint(),
+            Sorter::new(
+                &Config {
+                    group_headers: true,
+                    ..Default::default()
+                },
+                Default::default()
+            )
+            .fingerprint()
+        );
+    }
+
+    #[test]
+    fn group_keys() {
+        for ty in ImportType::ALL {
+            assert_eq!(ImportType::from_key(ty.key()), Some(ty));
+        }
+        assert_eq!(ImportType::from_key("modules"), None);
+    }
+}
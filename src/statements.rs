@@ -1,13 +1,22 @@
 mod comment;
+mod directive;
 mod imports;
+mod namespace_alias;
 
 use std::fmt::Display;
 
 pub use comment::*;
+pub use directive::TripleSlashDirective;
 pub use imports::*;
+pub use namespace_alias::NamespaceAlias;
 
 pub trait Statement {
     fn code(&self) -> String;
+
+    /// Whether the statement is code rather than a comment.
+    fn is_code(&self) -> bool {
+        false
+    }
 }
 
 impl Display for dyn Statement {
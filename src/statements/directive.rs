@@ -0,0 +1,16 @@
+use super::Statement;
+
+/// A TypeScript triple-slash directive, like `/// <reference types="vite/client" />`.
+///
+/// Directives are only valid at the top of a file, so they are always written first.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TripleSlashDirective {
+    /// The directive after the slashes, like ` <reference types="vite/client" />`.
+    pub data: String,
+}
+
+impl Statement for TripleSlashDirective {
+    fn code(&self) -> String {
+        format!("///{}", self.data.trim_end())
+    }
+}
@@ -1,5 +1,7 @@
 mod import_statement;
 mod import_type;
 
-pub use import_statement::ImportStatement;
-pub use import_type::ImportType;
+pub use import_statement::{ImportKind, ImportStatement, TypeOnly};
+pub use import_type::{
+    Extensions, ImportType, ASSET_EXTENSIONS, BUILTIN_MODULES, STYLE_EXTENSIONS,
+};
@@ -1,49 +1,104 @@
-use std::{cmp::Ordering, fmt::Debug};
+use std::fmt::Debug;
 
 use crate::statements::{Comment, Statement};
 
-use super::ImportType;
-
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ImportStatement {
     pub identifiers: Option<String>,
     pub module: String,
     pub comment: Option<Comment>,
+    pub kind: ImportKind,
+    pub type_only: Option<TypeOnly>,
 }
 
-impl ImportStatement {
-    pub fn ty(&self) -> ImportType {
-        ImportType::from(self.module.as_str())
-    }
+/// The syntax of an import.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ImportKind {
+    /// An ES module import, like `import x from 'x';`.
+    #[default]
+    Esm,
+    /// A TypeScript `import x = require('x');`, exported with `export import`.
+    Require { export: bool },
+}
+
+/// The keyword of a type-only import, like `import type x from 'x';`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum TypeOnly {
+    /// A TypeScript or Flow `import type`.
+    Type,
+    /// A Flow `import typeof`.
+    Typeof,
 }
 
-impl PartialOrd for ImportStatement {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        (self.ty(), &self.module).partial_cmp(&(other.ty(), &other.module))
+impl TypeOnly {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Type => "type",
+            Self::Typeof => "typeof",
+        }
     }
 }
 
-impl Ord for ImportStatement {
-    fn cmp(&self, other: &Self) -> Ordering {
-        (self.ty(), &self.module).cmp(&(other.ty(), &other.module))
+impl ImportStatement {
+    /// Returns the identifiers, preceded by the type-only keyword if any.
+    fn typed_identifiers(&self) -> Option<String> {
+        let identifiers = self.identifiers.as_deref()?;
+
+        Some(match self.type_only {
+            Some(type_only) => format!("{} {}", type_only.keyword(), identifiers),
+            None => identifiers.to_owned(),
+        })
+    }
+
+    /// Returns the module as a string literal.
+    ///
+    /// Single quotes are used, unless the module contains one which is not escaped.
+    fn quoted_module(&self) -> String {
+        let mut chars = self.module.chars();
+        let mut single_quote = false;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '\'' => single_quote = true,
+                _ => (),
+            }
+        }
+
+        match single_quote {
+            true => format!("\"{}\"", self.module),
+            false => format!("'{}'", self.module),
+        }
     }
 }
 
 impl Statement for ImportStatement {
     fn code(&self) -> String {
-        if let Some(id) = &self.identifiers {
+        if let ImportKind::Require { export } = self.kind {
+            format!(
+                "{}import {} = require({});{}",
+                if export { "export " } else { "" },
+                self.typed_identifiers().unwrap_or_default(),
+                self.quoted_module(),
+                self.comment
+                    .as_ref()
+                    .map_or(String::new(), |c| " ".to_owned() + &c.code())
+            )
+        } else if let Some(id) = self.typed_identifiers() {
             format!(
-                "import {} from '{}';{}",
+                "import {} from {};{}",
                 id,
-                self.module,
+                self.quoted_module(),
                 self.comment
                     .as_ref()
                     .map_or(String::new(), |c| " ".to_owned() + &c.code())
             )
         } else {
             format!(
-                "import '{}';{}",
-                self.module,
+                "import {};{}",
+                self.quoted_module(),
                 self.comment
                     .as_ref()
                     .map_or(String::new(), |c| " ".to_owned() + &c.code())
@@ -55,6 +110,7 @@ impl Statement for ImportStatement {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sorter::Sorter;
 
     #[test]
     fn module_sort() {
@@ -62,13 +118,62 @@ mod tests {
             identifiers: None,
             module: "abc".to_owned(),
             comment: None,
+            kind: ImportKind::Esm,
+            type_only: None,
         };
         let import2 = ImportStatement {
             identifiers: None,
             module: "xyz".to_owned(),
             comment: None,
+            kind: ImportKind::Esm,
+            type_only: None,
+        };
+
+        assert_eq!(
+            Sorter::default().sort(&[import2.clone(), import1.clone()]),
+            [import1, import2]
+        );
+    }
+
+    #[test]
+    fn require_code() {
+        let import = ImportStatement {
+            identifiers: Some("fs".to_owned()),
+            module: "fs".to_owned(),
+            comment: None,
+            kind: ImportKind::Require { export: true },
+            type_only: None,
+        };
+
+        assert_eq!(import.code(), "export import fs = require('fs');");
+    }
+
+    #[test]
+    fn quoted_module() {
+        let import = |module: &str| ImportStatement {
+            identifiers: None,
+            module: module.to_owned(),
+            comment: None,
+            kind: ImportKind::Esm,
+            type_only: None,
+        };
+
+        assert_eq!(import("./\\u00e9").code(), r"import './\u00e9';");
+        assert_eq!(import(r"it\'s").code(), r"import 'it\'s';");
+        assert_eq!(import("it's").code(), r#"import "it's";"#);
+        assert_eq!(import(r#"say "hi""#).code(), r#"import 'say "hi"';"#);
+    }
+
+    #[test]
+    fn type_only_code() {
+        let import = ImportStatement {
+            identifiers: Some("Foo".to_owned()),
+            module: "./foo".to_owned(),
+            comment: None,
+            kind: ImportKind::Esm,
+            type_only: Some(TypeOnly::Typeof),
         };
 
-        assert!(import1 < import2);
+        assert_eq!(import.code(), "import typeof Foo from './foo';");
     }
 }
@@ -1,6 +1,69 @@
+/// Node.js built-in modules, which can be imported without the `node:` prefix.
+pub const BUILTIN_MODULES: [&str; 54] = [
+    "assert",
+    "assert/strict",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "dns/promises",
+    "domain",
+    "events",
+    "fs",
+    "fs/promises",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "inspector/promises",
+    "module",
+    "net",
+    "os",
+    "path",
+    "path/posix",
+    "path/win32",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "readline/promises",
+    "repl",
+    "stream",
+    "stream/consumers",
+    "stream/promises",
+    "stream/web",
+    "string_decoder",
+    "sys",
+    "timers",
+    "timers/promises",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "util/types",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
 /// Distinguish the types of imports.
-#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub enum ImportType {
+    /// Node.js built-in module.
+    ///
+    /// Either a module of [BUILTIN_MODULES] or any module with the `node:` prefix,
+    /// like `fs` or `node:test`.
+    Builtin,
     /// Global import.
     ///
     /// This includes lodash, React
@@ -19,25 +82,97 @@ pub enum ImportType {
     /// Basically every module that does not fall into
     /// [Global](ImportType::Global) or [ScopedModule](ImportType::ScopedModule).
     Module,
+    /// A package of the current workspace.
+    ///
+    /// Workspace packages are declared by the npm / yarn `workspaces` of the root `package.json`
+    /// or by `pnpm-workspace.yaml`, and with the `workspace:` protocol in dependencies.
+    Workspace,
     /// An alias for a import
+    ///
+    /// Either `@` / `@/`, or a module matching the `paths` or resolvable through the `baseUrl`
+    /// of the nearest `tsconfig.json` / `jsconfig.json`.
     Alias,
     /// Project local import
     Local,
-    /// Stylesheet import
+    /// CSS module import, like `import styles from './button.module.scss'`.
+    ///
+    /// A stylesheet of the [style extensions](Extensions::styles) named `*.module.*`.
+    StyleModule,
+    /// Stylesheet import, like `import './global.scss'`.
+    ///
+    /// A module ending with one of the [style extensions](Extensions::styles).
     Style,
+    /// Asset import, like images, fonts or SVGs.
+    ///
+    /// A module ending with one of the [asset extensions](Extensions::assets).
+    Asset,
 }
 
-impl From<&str> for ImportType {
-    fn from(value: &str) -> Self {
-        if value == "@" || value.starts_with("@/") {
+impl ImportType {
+    /// Every import type, in order.
+    pub const ALL: [ImportType; 10] = [
+        Self::Builtin,
+        Self::Global,
+        Self::ScopedModule,
+        Self::Module,
+        Self::Workspace,
+        Self::Alias,
+        Self::Local,
+        Self::StyleModule,
+        Self::Style,
+        Self::Asset,
+    ];
+
+    /// Parses the key of a group, like `module` or `style-module`, see [ImportType::key].
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ty| ty.key() == key)
+    }
+
+    /// Key of the group of this type, naming it in the options.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Builtin => "builtin",
+            Self::Global => "global",
+            Self::ScopedModule => "scoped-module",
+            Self::Module => "module",
+            Self::Workspace => "workspace",
+            Self::Alias => "alias",
+            Self::Local => "local",
+            Self::StyleModule => "style-module",
+            Self::Style => "style",
+            Self::Asset => "asset",
+        }
+    }
+
+    /// Name of the group of this type, used in generated group headers by default.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Builtin => "Built-in modules",
+            Self::Global => "Global modules",
+            Self::ScopedModule => "Scoped modules",
+            Self::Module => "Modules",
+            Self::Workspace => "Workspace packages",
+            Self::Alias => "Aliases",
+            Self::Local => "Local imports",
+            Self::StyleModule => "CSS modules",
+            Self::Style => "Styles",
+            Self::Asset => "Assets",
+        }
+    }
+
+    /// Classifies a module, using `extensions` to recognize stylesheets and assets.
+    pub fn classify(value: &str, extensions: &Extensions) -> Self {
+        if value.starts_with("node:") || BUILTIN_MODULES.contains(&value) {
+            Self::Builtin
+        } else if value == "@" || value.starts_with("@/") {
             Self::Alias
-        } else if value.ends_with(".css") {
-            Self::Style
+        } else if let Some(ty) = extensions.classify(value) {
+            ty
         } else if value.starts_with('@') {
             Self::ScopedModule
         } else if value.starts_with('.') {
             Self::Local
-        } else if vec!["react", "lodash", "prop-types"].contains(&value) {
+        } else if ["react", "lodash", "prop-types"].contains(&value) {
             Self::Global
         } else {
             Self::Module
@@ -45,23 +180,118 @@ impl From<&str> for ImportType {
     }
 }
 
+impl From<&str> for ImportType {
+    fn from(value: &str) -> Self {
+        Self::classify(value, &Extensions::default())
+    }
+}
+
+/// File extensions recognizing [Style](ImportType::Style) and [Asset](ImportType::Asset)
+/// imports, without the leading dot.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Extensions {
+    /// Extensions of stylesheets, like `css` or `scss`.
+    pub styles: Vec<String>,
+    /// Extensions of assets, like `svg` or `woff2`.
+    pub assets: Vec<String>,
+}
+
+impl Extensions {
+    /// Returns the type of a module by its extension, if it is a stylesheet or an asset.
+    ///
+    /// Query strings and fragments, like `./logo.svg?url`, are ignored.
+    pub fn classify(&self, module: &str) -> Option<ImportType> {
+        let path = module.split(['?', '#']).next().unwrap_or(module);
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let (stem, extension) = name.rsplit_once('.')?;
+        let matches = |extensions: &[String]| {
+            extensions
+                .iter()
+                .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(extension))
+        };
+
+        if stem.is_empty() || extension.is_empty() {
+            None
+        } else if matches(&self.styles) {
+            if stem.ends_with(".module") {
+                Some(ImportType::StyleModule)
+            } else {
+                Some(ImportType::Style)
+            }
+        } else if matches(&self.assets) {
+            Some(ImportType::Asset)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Self {
+            styles: STYLE_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            assets: ASSET_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+/// Default extensions of stylesheets.
+pub const STYLE_EXTENSIONS: [&str; 7] = ["css", "scss", "sass", "less", "styl", "pcss", "sss"];
+
+/// Default extensions of assets: images, fonts and media.
+pub const ASSET_EXTENSIONS: [&str; 19] = [
+    "svg", "png", "jpg", "jpeg", "gif", "webp", "avif", "ico", "bmp", "woff", "woff2", "ttf",
+    "otf", "eot", "mp4", "webm", "mp3", "wav", "ogg",
+];
+
 #[cfg(test)]
 mod tests {
-    use super::ImportType::{self, *};
+    use super::{
+        Extensions,
+        ImportType::{self, *},
+    };
+
+    #[test]
+    fn builtin_first() {
+        assert!(Builtin < Global);
+    }
 
     #[test]
     fn global_before_style() {
         assert!(Global < Style);
     }
 
+    #[test]
+    fn styles_and_assets_last() {
+        assert!(Local < StyleModule);
+        assert!(StyleModule < Style);
+        assert!(Style < Asset);
+    }
+
     #[test]
     fn module_before_local() {
         assert!(Module < Local);
     }
 
+    #[test]
+    fn workspace_between_module_and_alias() {
+        assert!(Module < Workspace);
+        assert!(Workspace < Alias);
+    }
+
     mod from {
         use super::*;
 
+        #[test]
+        fn builtin() {
+            assert_eq!(ImportType::from("fs"), Builtin);
+            assert_eq!(ImportType::from("fs/promises"), Builtin);
+            assert_eq!(ImportType::from("node:crypto"), Builtin);
+            assert_eq!(ImportType::from("node:test"), Builtin);
+            assert_eq!(ImportType::from("fs-extra"), Module);
+            assert_eq!(ImportType::from("test"), Module);
+        }
+
         #[test]
         fn local_alias() {
             assert_eq!(ImportType::from("@/components"), Alias);
@@ -83,6 +313,38 @@ mod tests {
             // Style is more important than scoped module import
             assert_eq!(ImportType::from("@react/style.css"), Style);
             assert_eq!(ImportType::from("components/style.css"), Style);
+            assert_eq!(ImportType::from("./theme.scss"), Style);
+            assert_eq!(ImportType::from("./theme.less"), Style);
+            assert_eq!(ImportType::from("bootstrap/dist/sass/main.sass"), Style);
+        }
+
+        #[test]
+        fn style_module() {
+            assert_eq!(ImportType::from("./button.module.css"), StyleModule);
+            assert_eq!(ImportType::from("./button.module.scss"), StyleModule);
+            assert_eq!(ImportType::from("./module.scss"), Style);
+        }
+
+        #[test]
+        fn asset() {
+            assert_eq!(ImportType::from("./logo.svg"), Asset);
+            assert_eq!(ImportType::from("../fonts/inter.woff2"), Asset);
+            assert_eq!(ImportType::from("./logo.svg?react"), Asset);
+            assert_eq!(ImportType::from("./logo.PNG"), Asset);
+            assert_eq!(ImportType::from("./svg"), Local);
+        }
+
+        #[test]
+        fn custom_extensions() {
+            let extensions = Extensions {
+                styles: vec!["pcss".to_owned()],
+                assets: vec![".glb".to_owned()],
+            };
+
+            assert_eq!(ImportType::classify("./main.pcss", &extensions), Style);
+            assert_eq!(ImportType::classify("./main.scss", &extensions), Local);
+            assert_eq!(ImportType::classify("./model.glb", &extensions), Asset);
+            assert_eq!(ImportType::classify("./logo.svg", &extensions), Local);
         }
 
         #[test]
@@ -0,0 +1,32 @@
+use super::{Comment, Statement};
+
+/// A TypeScript namespace alias, like `import Foo = Bar.Baz;`.
+///
+/// Aliases depend on the imports before them, so they are kept where they are.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NamespaceAlias {
+    /// Whether the alias is exported, with `export import`.
+    pub export: bool,
+    pub name: String,
+    /// The aliased entity, like `Bar.Baz`.
+    pub entity: String,
+    pub comment: Option<Comment>,
+}
+
+impl Statement for NamespaceAlias {
+    fn code(&self) -> String {
+        format!(
+            "{}import {} = {};{}",
+            if self.export { "export " } else { "" },
+            self.name,
+            self.entity,
+            self.comment
+                .as_ref()
+                .map_or(String::new(), |c| " ".to_owned() + &c.code())
+        )
+    }
+
+    fn is_code(&self) -> bool {
+        true
+    }
+}